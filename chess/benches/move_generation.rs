@@ -0,0 +1,22 @@
+use chess_lib::{fen::STARTING_FEN, game::Game};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+/// Legal movement generation with and without the bitboard backend
+fn legal_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("legal_moves");
+    for (name, fen) in [("start", STARTING_FEN), ("kiwipete", KIWIPETE)] {
+        for (backend, enabled) in [("squares", false), ("bitboards", true)] {
+            let mut game = Game::from_fen(fen).expect("Valid FEN");
+            game.board.use_bitboards(enabled);
+            group.bench_function(BenchmarkId::new(backend, name), |b| {
+                b.iter(|| game.legal_moves())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, legal_moves);
+criterion_main!(benches);
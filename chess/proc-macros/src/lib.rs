@@ -1,6 +1,11 @@
 extern crate proc_macro;
 
-#[proc_macro_derive(Piece)]
+/// Symbols of the built-in `PieceKind`s, which custom pieces cannot take
+const BUILT_IN_SYMBOLS: &str = "KQRBNPAC";
+/// Movement patterns a custom piece can combine
+const MOVES: [&str; 4] = ["king", "rook", "bishop", "knight"];
+
+#[proc_macro_derive(Piece, attributes(piece))]
 pub fn new_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     // Parse the input tokens into a syntax tree
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
@@ -8,6 +13,13 @@ pub fn new_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     // Get the name of the type we are deriving for
     let name = input.ident;
 
+    // The `PieceKind` of the same name, unless `#[piece(...)]` declares another one
+    let kind = match piece_kind(&input.attrs) {
+        Ok(Some(kind)) => kind,
+        Ok(None) => quote::quote! { PieceKind::#name },
+        Err(error) => return error.to_compile_error().into(),
+    };
+
     // Generate the implementation of the trait
     let expanded = quote::quote! {
         impl Piece for #name {
@@ -17,9 +29,80 @@ pub fn new_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
           fn color(&self) -> Color {
             self.0
           }
+          fn kind(&self) -> PieceKind {
+            #kind
+          }
         }
     };
 
     // Convert the generated code into a proc_macro::TokenStream and return it
     proc_macro::TokenStream::from(expanded)
 }
+
+/// Parses `#[piece(kind = Knight)]` into `PieceKind::Knight`, and
+/// `#[piece(symbol = 'W', moves(king, knight))]` into a `PieceKind::Custom`
+fn piece_kind(attrs: &[syn::Attribute]) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    let mut kind: Option<syn::Ident> = None;
+    let mut symbol: Option<syn::LitChar> = None;
+    let mut moves = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("piece")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("kind") {
+                kind = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("symbol") {
+                symbol = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("moves") {
+                meta.parse_nested_meta(|pattern| {
+                    match pattern.path.get_ident() {
+                        Some(ident) if MOVES.contains(&ident.to_string().as_str()) => {
+                            moves.push(ident.clone())
+                        }
+                        _ => return Err(pattern.error("expected king, rook, bishop or knight")),
+                    }
+                    Ok(())
+                })?;
+            } else {
+                return Err(meta.error("expected kind, symbol or moves"));
+            }
+            Ok(())
+        })?;
+    }
+
+    match (kind, symbol) {
+        (None, None) if moves.is_empty() => Ok(None),
+        (Some(kind), None) if moves.is_empty() => Ok(Some(quote::quote! { PieceKind::#kind })),
+        (None, Some(symbol)) => {
+            let value = symbol.value();
+            if !value.is_ascii_uppercase() || BUILT_IN_SYMBOLS.contains(value) {
+                return Err(syn::Error::new(
+                    symbol.span(),
+                    format!("symbol must be an uppercase letter other than {BUILT_IN_SYMBOLS}"),
+                ));
+            }
+            if moves.is_empty() {
+                return Err(syn::Error::new(
+                    symbol.span(),
+                    "custom pieces need moves(...)",
+                ));
+            }
+            let flags = MOVES.map(|pattern| moves.iter().any(|ident| ident == pattern));
+            let [king, rook, bishop, knight] = flags;
+            Ok(Some(quote::quote! {
+                PieceKind::Custom {
+                    symbol: #symbol,
+                    moves: Moves {
+                        king: #king,
+                        rook: #rook,
+                        bishop: #bishop,
+                        knight: #knight,
+                    },
+                }
+            }))
+        }
+        _ => Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "use either #[piece(kind = ...)] or #[piece(symbol = ..., moves(...))]",
+        )),
+    }
+}
@@ -0,0 +1,534 @@
+//! Bitboard module.
+//!
+//! 64-bit representation of an 8x8 board, used by `Board` to generate the movements of every
+//! piece and to detect attacks
+//!
+use super::{
+    board::Position,
+    pieces::{ChessPiece, Color, PieceKind},
+};
+use serde::{Deserialize, Serialize};
+use std::{ops::Range, sync::OnceLock};
+
+/// Set of squares of an 8x8 board: bit `8 * y + x` for every `Position`
+pub type Bitboard = u64;
+
+/// `(x, y)` offsets of the `Knight` jumps
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+/// `(x, y)` offsets of the `King` steps
+const KING_OFFSETS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+/// Sliding directions: the first four are the `Rook` ones and the last four the `Bishop` ones
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 0),
+    (0, -1),
+    (-1, 0),
+    (1, 1),
+    (-1, 1),
+    (1, -1),
+    (-1, -1),
+];
+
+/// Squares attacked by a `Knight` from every square
+const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+/// Squares attacked by a `King` from every square
+const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_OFFSETS);
+/// Squares attacked by a `White` and a `Black` `Pawn` from every square
+const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&[(1, 1), (-1, 1)]),
+    leaper_attacks(&[(1, -1), (-1, -1)]),
+];
+/// Squares from every square to the edge of the board in every direction, excluding the
+/// origin
+const RAYS: [[Bitboard; 64]; 8] = rays();
+/// `Rook` and `Bishop` directions in `DIRECTIONS`
+const ROOK_DIRECTIONS: Range<usize> = 0..4;
+const BISHOP_DIRECTIONS: Range<usize> = 4..8;
+/// `Magic` multipliers of the `Rook` in every square, found trying sparse pseudo-random numbers
+const ROOK_MAGICS: [u64; 64] = [
+    0x0080008020104004,
+    0x0200208042001104,
+    0x008020008010000b,
+    0x0200060008104020,
+    0x0100030004080010,
+    0x0880020044008001,
+    0x0480008001000200,
+    0x0200002404408112,
+    0x0000800080204000,
+    0x2030400040201000,
+    0x6008801000200080,
+    0x0040800800100081,
+    0x00e1000800050010,
+    0x800b000884010012,
+    0x8308808001000200,
+    0x5402002400410c82,
+    0x80008380024002e1,
+    0x012000c008500120,
+    0x0209010020001044,
+    0x4000808010000800,
+    0x8040808008000400,
+    0x804d010002080400,
+    0x0000440002415008,
+    0x0024020003048a44,
+    0x0000400080208000,
+    0x0041048300400020,
+    0x0140100080200085,
+    0x84014092000a00a2,
+    0x0442013200042048,
+    0x0102040080020080,
+    0x8001000700140a00,
+    0x0000218200040641,
+    0x0080002000400050,
+    0x0010012001404001,
+    0x0d09001041002000,
+    0x1220100101002008,
+    0x1644004008080080,
+    0x0000100408012040,
+    0x4900c10204001048,
+    0x4018310082000044,
+    0x4010401080208006,
+    0x0000201000404000,
+    0x1000108202460020,
+    0x0480401022020008,
+    0x0203110801010004,
+    0x4082000411020008,
+    0xc010020810040001,
+    0x3801018441020034,
+    0x10a7800040106280,
+    0x0000804000201080,
+    0x0002004051836600,
+    0x4801001000200900,
+    0x011e080104008080,
+    0x2082000204008080,
+    0x0810814210080400,
+    0x0046a0ca8c050200,
+    0x0400208001001041,
+    0x40808c2100104001,
+    0x8002000880201442,
+    0x101600044110200a,
+    0x4009001044080003,
+    0x0001000400080201,
+    0x2000411002084084,
+    0x1001000214c18021,
+];
+/// `Magic` multipliers of the `Bishop` in every square
+const BISHOP_MAGICS: [u64; 64] = [
+    0x6410040104002200,
+    0x00081007060c2604,
+    0x0130341680300602,
+    0x82020a0208000204,
+    0x0054030808000000,
+    0x810e061004a04080,
+    0x0811011010041000,
+    0xb025050045144001,
+    0x40200a102c0a8400,
+    0x00031102008c0080,
+    0x8000880081020860,
+    0x200008a082000b20,
+    0x804001104080c204,
+    0x00a0008844401902,
+    0x00004084c4104080,
+    0x0000802898041002,
+    0x0142040404680200,
+    0x2004000801042400,
+    0x041a401803810202,
+    0x0400800410220000,
+    0x4c8c004822082160,
+    0x0202020100410400,
+    0x0100420104500442,
+    0x4104802114008200,
+    0x00020a4840081808,
+    0x08042200a0120400,
+    0x000b880440444500,
+    0x0004040020101010,
+    0x0821010081104000,
+    0x0000a10002005200,
+    0x0008940805010800,
+    0x2002004002090081,
+    0x4010841160441008,
+    0x0408221000080101,
+    0x0000405004080220,
+    0x50801108004c0040,
+    0x000c0c0400305100,
+    0x0202101210150040,
+    0x000401020ec04801,
+    0x0101041220008200,
+    0x00408a2060001000,
+    0x0200410820200800,
+    0x0000202028005000,
+    0x0002108420200400,
+    0x8108088301414400,
+    0x1020040100cc0a00,
+    0x0018d00082800e00,
+    0x00040c0400300044,
+    0x0000808420206000,
+    0x6102808090110500,
+    0x0200024224900000,
+    0x5804010104882008,
+    0x0400121002020888,
+    0x050b122041290000,
+    0x20c008055c018002,
+    0x002004014a042020,
+    0x8200802082104048,
+    0xa00800804808a408,
+    0x0004161125082820,
+    0x1100200800840402,
+    0x6006000220020482,
+    0x20008104101a3203,
+    0x0082101002008424,
+    0x1020620a08010010,
+];
+
+/// Magic bitboard tables of the sliding pieces, built on first use
+static SLIDERS: OnceLock<Sliders> = OnceLock::new();
+
+/// Attacks of the `Rook` and the `Bishop` from every square, for every set of blockers
+struct Sliders {
+    rook: Vec<Magic>,
+    bishop: Vec<Magic>,
+    /// Attacks of every square, from the `offset` of its `Magic`
+    attacks: Vec<Bitboard>,
+}
+
+/// Perfect hash of the blockers of a sliding piece in a square
+struct Magic {
+    /// Squares whose pieces can block it: its rays, without the last square of each
+    mask: Bitboard,
+    /// Multiplier mapping the blockers in `mask` to a different index for different attacks
+    magic: u64,
+    /// `64` minus the squares of `mask`, so the index has a bit per square of it
+    shift: u32,
+    /// Start of the attacks of the square in `Sliders::attacks`
+    offset: usize,
+}
+
+impl Magic {
+    /// Returns the index of the attacks with some occupied squares in `Sliders::attacks`
+    fn index(&self, occupied: Bitboard) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+/// Bitboards of the pieces of each `PieceKind` and `Color`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Bitboards {
+    /// Squares of every `PieceKind`, by `Color`
+    pieces: [[Bitboard; 8]; 2],
+    /// Squares occupied by every `Color`
+    colors: [Bitboard; 2],
+}
+
+impl Bitboards {
+    /// Add a piece in a `Position`
+    pub fn put(&mut self, position: Position, piece: ChessPiece) {
+        let bit = bit(position);
+        self.pieces[color_index(piece.color)][kind_index(piece.kind)] |= bit;
+        self.colors[color_index(piece.color)] |= bit;
+    }
+
+    /// Remove a piece from a `Position`
+    pub fn remove(&mut self, position: Position, piece: ChessPiece) {
+        let bit = !bit(position);
+        self.pieces[color_index(piece.color)][kind_index(piece.kind)] &= bit;
+        self.colors[color_index(piece.color)] &= bit;
+    }
+
+    /// Returns the squares of the pieces of a `PieceKind` and `Color`
+    pub fn pieces(&self, kind: PieceKind, color: Color) -> Bitboard {
+        self.pieces[color_index(color)][kind_index(kind)]
+    }
+
+    /// Returns the squares occupied by a `Color`
+    pub fn color(&self, color: Color) -> Bitboard {
+        self.colors[color_index(color)]
+    }
+
+    /// Returns the occupied squares
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// Returns the squares a piece attacks from a `Position`. For pawns, only the diagonal
+    /// captures
+    pub fn attacks(&self, from: Position, piece: ChessPiece) -> Bitboard {
+        let square = index(from);
+        let occupied = self.occupied();
+        if piece.kind == PieceKind::Pawn {
+            return PAWN_ATTACKS[color_index(piece.color)][square];
+        }
+        let moves = piece.kind.moves();
+        let pattern = |enabled: bool, attacks: Bitboard| if enabled { attacks } else { 0 };
+        pattern(moves.king, KING_ATTACKS[square])
+            | pattern(moves.rook, rook_attacks(square, occupied))
+            | pattern(moves.bishop, bishop_attacks(square, occupied))
+            | pattern(moves.knight, KNIGHT_ATTACKS[square])
+    }
+
+    /// Returns the squares a `Pawn` can move to from a `Position`: one square forward, or two
+    /// if `double_step`, to empty squares, and diagonal captures of rival pieces or en passant
+    pub fn pawn_moves(
+        &self,
+        from: Position,
+        color: Color,
+        double_step: bool,
+        en_passant: Option<Position>,
+    ) -> Bitboard {
+        let square = index(from);
+        let empty = !self.occupied();
+        let forward = |bitboard: Bitboard| match color {
+            Color::White => bitboard << 8,
+            Color::Black => bitboard >> 8,
+        };
+        let single = forward(1 << square) & empty;
+        let double = if double_step {
+            forward(single) & empty
+        } else {
+            0
+        };
+        let captures = self.color(!color) | en_passant.map_or(0, bit);
+        single | double | PAWN_ATTACKS[color_index(color)][square] & captures
+    }
+
+    /// Returns `true` if any piece of `attacker` color attacks a `Position`
+    pub fn is_attacked(&self, position: Position, attacker: Color) -> bool {
+        let square = index(position);
+        let occupied = self.occupied();
+        let pieces = |kind| self.pieces(kind, attacker);
+        let straight =
+            pieces(PieceKind::Rook) | pieces(PieceKind::Queen) | pieces(PieceKind::Chancellor);
+        let diagonal =
+            pieces(PieceKind::Bishop) | pieces(PieceKind::Queen) | pieces(PieceKind::Archbishop);
+        let knights = pieces(PieceKind::Knight)
+            | pieces(PieceKind::Archbishop)
+            | pieces(PieceKind::Chancellor);
+
+        // A `Pawn` attacks a square from where a rival `Pawn` in that square would attack
+        KNIGHT_ATTACKS[square] & knights != 0
+            || KING_ATTACKS[square] & pieces(PieceKind::King) != 0
+            || PAWN_ATTACKS[color_index(!attacker)][square] & pieces(PieceKind::Pawn) != 0
+            || rook_attacks(square, occupied) & straight != 0
+            || bishop_attacks(square, occupied) & diagonal != 0
+    }
+
+    /// Returns `true` if moving a piece, capturing the one in `captured` if any, does not leave
+    /// its `King` in check. It does not move the `Rook` of castles
+    pub fn keeps_king_safe(
+        &self,
+        from: Position,
+        to: Position,
+        piece: ChessPiece,
+        captured: Option<(Position, ChessPiece)>,
+    ) -> bool {
+        let mut after = *self;
+        if let Some((position, captured)) = captured {
+            after.remove(position, captured);
+        }
+        after.remove(from, piece);
+        after.put(to, piece);
+        positions(after.pieces(PieceKind::King, piece.color))
+            .all(|king| !after.is_attacked(king, !piece.color))
+    }
+}
+
+/// Returns the `Position`s of the squares of a `Bitboard`
+pub fn positions(mut bitboard: Bitboard) -> impl Iterator<Item = Position> {
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+        let square = bitboard.trailing_zeros() as i32;
+        bitboard &= bitboard - 1;
+        Some(Position {
+            x: square % 8,
+            y: square / 8,
+        })
+    })
+}
+
+/// Returns the `Bitboard` with only the square of a `Position`
+pub fn bit(position: Position) -> Bitboard {
+    1 << index(position)
+}
+
+/// Returns the square index of a `Position`
+fn index(position: Position) -> usize {
+    (8 * position.y + position.x) as usize
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn kind_index(kind: PieceKind) -> usize {
+    match kind {
+        PieceKind::King => 0,
+        PieceKind::Queen => 1,
+        PieceKind::Rook => 2,
+        PieceKind::Bishop => 3,
+        PieceKind::Knight => 4,
+        PieceKind::Pawn => 5,
+        PieceKind::Archbishop => 6,
+        PieceKind::Chancellor => 7,
+        PieceKind::Custom { .. } => unreachable!("Boards with custom pieces have no bitboards"),
+    }
+}
+
+/// Squares attacked by a `Rook` in a square
+fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    let sliders = sliders();
+    sliders.attacks[sliders.rook[square].index(occupied)]
+}
+
+/// Squares attacked by a `Bishop` in a square
+fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    let sliders = sliders();
+    sliders.attacks[sliders.bishop[square].index(occupied)]
+}
+
+/// Returns the magic bitboard tables, building them the first time
+fn sliders() -> &'static Sliders {
+    SLIDERS.get_or_init(|| {
+        let mut attacks = Vec::new();
+        let mut magics = |directions: Range<usize>, magics: &[u64; 64]| -> Vec<Magic> {
+            (0..64)
+                .map(|square| magic(square, directions.clone(), magics[square], &mut attacks))
+                .collect()
+        };
+        let rook = magics(ROOK_DIRECTIONS, &ROOK_MAGICS);
+        let bishop = magics(BISHOP_DIRECTIONS, &BISHOP_MAGICS);
+        Sliders {
+            rook,
+            bishop,
+            attacks,
+        }
+    })
+}
+
+/// Builds the `Magic` of a sliding piece in a square, appending its attacks for every subset
+/// of blockers to `attacks`
+fn magic(
+    square: usize,
+    directions: Range<usize>,
+    magic: u64,
+    attacks: &mut Vec<Bitboard>,
+) -> Magic {
+    let mask = directions.clone().fold(0, |mask, direction| {
+        let ray = RAYS[direction][square];
+        mask | ray & !ray_end(ray, direction)
+    });
+    let magic = Magic {
+        mask,
+        magic,
+        shift: 64 - mask.count_ones(),
+        offset: attacks.len(),
+    };
+    attacks.resize(magic.offset + (1 << mask.count_ones()), 0);
+
+    // Walk every subset of `mask`, starting and ending with the empty one
+    let mut blockers: Bitboard = 0;
+    loop {
+        let expected = directions.clone().fold(0, |attacks, direction| {
+            attacks | ray_attacks(square, blockers, direction)
+        });
+        let entry = &mut attacks[magic.index(blockers)];
+        debug_assert!(*entry == 0 || *entry == expected, "Magic collision");
+        *entry = expected;
+        blockers = blockers.wrapping_sub(mask) & mask;
+        if blockers == 0 {
+            return magic;
+        }
+    }
+}
+
+/// Returns the last square of a ray in a direction: the blockers there do not change the
+/// attacks, so they are left out of the `Magic` masks
+fn ray_end(ray: Bitboard, direction: usize) -> Bitboard {
+    let (x, y) = DIRECTIONS[direction];
+    if ray == 0 {
+        0
+    } else if 8 * y + x > 0 {
+        1 << (63 - ray.leading_zeros())
+    } else {
+        ray & ray.wrapping_neg()
+    }
+}
+
+/// Squares attacked sliding from a square in a direction, up to (and including) the first
+/// occupied one. Only used to build the `Magic` tables
+fn ray_attacks(square: usize, occupied: Bitboard, direction: usize) -> Bitboard {
+    let ray = RAYS[direction][square];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+    // Directions increasing the square index find their nearest blocker in the lowest bit
+    let (x, y) = DIRECTIONS[direction];
+    let blocker = if 8 * y + x > 0 {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    ray & !RAYS[direction][blocker as usize]
+}
+
+/// Precompute the attacks of a piece jumping at some `(x, y)` offsets from every square
+const fn leaper_attacks(offsets: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < offsets.len() {
+            let x = square as i32 % 8 + offsets[i].0;
+            let y = square as i32 / 8 + offsets[i].1;
+            if x >= 0 && x < 8 && y >= 0 && y < 8 {
+                attacks[square] |= 1 << (8 * y + x);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    attacks
+}
+
+/// Precompute the squares from every square to the edge of the board in every direction
+const fn rays() -> [[Bitboard; 64]; 8] {
+    let mut rays = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (step_x, step_y) = DIRECTIONS[direction];
+        let mut square = 0;
+        while square < 64 {
+            let mut x = square as i32 % 8 + step_x;
+            let mut y = square as i32 / 8 + step_y;
+            while x >= 0 && x < 8 && y >= 0 && y < 8 {
+                rays[direction][square] |= 1 << (8 * y + x);
+                x += step_x;
+                y += step_y;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    rays
+}
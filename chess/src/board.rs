@@ -7,19 +7,31 @@ use self::{
     MovementKind::{Diagonal, Horizontal, Knight as KnightMovement, Vertical},
 };
 use super::{
-    ensure,
+    bitboard::{self, Bitboards},
+    ensure, fail,
     pieces::{
+        ChessPiece,
         Color::{self, Black, White},
         Piece,
+        PieceKind::{Archbishop, Bishop, Chancellor, Custom, King, Knight, Pawn, Queen, Rook},
+        Promotion,
     },
+    zobrist,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use std::{
     collections::{HashMap, HashSet},
     error::Error,
     fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
 };
 
+/// Files that can be named by a square, from `a` to `z`
+pub const MAX_FILES: i32 = 26;
+/// Ranks that can be named by up to two digits
+pub const MAX_RANKS: i32 = 99;
+
 /// List of Errors for disallowed movements
 #[derive(Debug, PartialEq, Eq)]
 pub enum MovementError {
@@ -37,6 +49,14 @@ pub enum MovementError {
     WrongCommand(String),
     /// Try to move an opponent's Piece
     WrongPiece(Color),
+    /// The `Game` has already ended
+    GameEnded,
+    /// A `Pawn` reaching the last row needs a promotion, which is not allowed for any other Movement
+    InvalidPromotion,
+    /// The Movement command matches more than one legal Movement
+    AmbiguousMovement(String),
+    /// There is already a Piece in that square
+    Occupied(Position),
 }
 
 impl fmt::Display for MovementError {
@@ -48,9 +68,22 @@ impl fmt::Display for MovementError {
             MovementError::Check => write!(f, "That would be check"),
             MovementError::NoPiece => write!(f, "There is not any piece in that square"),
             MovementError::WrongPiece(color) => write!(f, "You can not play {:?} pieces", color),
+            MovementError::GameEnded => write!(f, "The game has already ended"),
+            MovementError::InvalidPromotion => {
+                write!(
+                    f,
+                    "Promotion is only allowed, and required, for pawns reaching the last row"
+                )
+            }
             MovementError::WrongCommand(command) => {
                 write!(f, "{:?} is not a valid movement command", command)
             }
+            MovementError::AmbiguousMovement(command) => {
+                write!(f, "{:?} matches more than one movement", command)
+            }
+            MovementError::Occupied(position) => {
+                write!(f, "There is already a piece in {}", position)
+            }
         }
     }
 }
@@ -64,11 +97,55 @@ pub struct Position {
     pub y: i32,
 }
 
+impl fmt::Display for Position {
+    /// Square name, e.g. `e4` for `Position { x: 4, y: 3 }`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.x as u8) as char, self.y + 1)
+    }
+}
+
+impl FromStr for Position {
+    type Err = MovementError;
+
+    /// Parse a square name, e.g. `e4` (or `E4`) for `Position { x: 4, y: 3 }`
+    fn from_str(square: &str) -> Result<Self, Self::Err> {
+        let wrong_square = || MovementError::WrongCommand(square.to_string());
+        let mut chars = square.chars();
+        let x = match chars.next() {
+            Some(file @ 'a'..='z') => file as i32 - 'a' as i32,
+            Some(file @ 'A'..='Z') => file as i32 - 'A' as i32,
+            _ => fail!(wrong_square()),
+        };
+        let rank = chars.as_str();
+        ensure!(
+            !rank.is_empty() && rank.chars().all(|c| c.is_ascii_digit()),
+            wrong_square()
+        );
+        let y = rank.parse::<i32>().map_err(|_| wrong_square())? - 1;
+        ensure!(y >= 0, wrong_square());
+
+        Ok(Position { x, y })
+    }
+}
+
 /// Movement representation between two Board squares
-#[derive(Debug)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Movement {
     pub from: Position,
     pub to: Position,
+    /// Piece a `Pawn` is promoted to when reaching the last row
+    pub promotion: Option<Promotion>,
+}
+
+impl fmt::Display for Movement {
+    /// Movement command, e.g. `e2e4` or `e7e8q`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.symbol())?;
+        }
+        Ok(())
+    }
 }
 
 /// Possible directions for `MovementKind`
@@ -90,82 +167,285 @@ pub enum MovementKind {
     Knight,
 }
 
+/// Information needed to revert a `Movement` already applied to the `Board`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct MovementRecord {
+    from: Position,
+    to: Position,
+    /// `Pawn` replaced by the promoted piece
+    promoted_pawn: Option<ChessPiece>,
+    /// Square and `Color` of the piece moved to `pieces_dead`
+    captured: Option<(Position, Color)>,
+    /// `Rook` origin and destination when castling
+    castle_rook: Option<(Position, Position)>,
+    /// `CastlingRights` of `White` and `Black`
+    castling_rights: [CastlingRights; 2],
+    en_passant: Option<Position>,
+}
+
+/// Castling availability of a `Color`
+///
+/// A side is lost as soon as the `King` or the corresponding `Rook` leave (or are captured in)
+/// their initial square
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct CastlingRights {
+    /// Castle towards the highest `x` (`Rook` in the `dimension.x` file)
+    pub king_side: bool,
+    /// Castle towards `x = 0` (`Rook` in the first file)
+    pub queen_side: bool,
+}
+
+/// Initial files of the `King` and the castling `Rook`s, which can be any in Chess960
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct CastlingFiles {
+    pub king: i32,
+    pub king_side_rook: i32,
+    pub queen_side_rook: i32,
+}
+
+impl CastlingFiles {
+    /// Castling files of a `King` in a certain file and the `Rook`s in the corners of a `Board`
+    pub fn corners(king: i32, dimension: Position) -> Self {
+        CastlingFiles {
+            king,
+            king_side_rook: dimension.x,
+            queen_side_rook: 0,
+        }
+    }
+
+    /// Returns `true` if castling can be played as in standard chess, moving the `King` to its
+    /// destination: the `Rook`s are in the corners and the `King` crosses at least two squares
+    /// towards either of them, e.g. `e1g1` on 8x8 or `f1i1` in Capablanca chess
+    pub fn is_standard(&self, dimension: Position) -> bool {
+        *self == CastlingFiles::corners(self.king, dimension)
+            && (4..=dimension.x - 3).contains(&self.king)
+    }
+}
+
+impl Default for CastlingRights {
+    fn default() -> Self {
+        CastlingRights {
+            king_side: true,
+            queen_side: true,
+        }
+    }
+}
+
+/// `Pawn` rules that vary with the `Layout`
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct PawnRules {
+    /// Whether a `Pawn` can move two squares from its starting row
+    pub double_step: bool,
+    /// Whether a `Pawn` can be promoted to a `Bishop`
+    pub bishop_promotion: bool,
+}
+
+impl Default for PawnRules {
+    fn default() -> Self {
+        PawnRules {
+            double_step: true,
+            bishop_promotion: true,
+        }
+    }
+}
+
+impl PawnRules {
+    /// Returns the pieces a `Pawn` can be promoted to
+    pub fn promotions(&self) -> Vec<Promotion> {
+        [
+            Promotion::Queen,
+            Promotion::Rook,
+            Promotion::Bishop,
+            Promotion::Knight,
+        ]
+        .into_iter()
+        .filter(|promotion| self.bishop_promotion || *promotion != Promotion::Bishop)
+        .collect()
+    }
+}
+
 /// Chess game Board reprentation
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// Only the position is serialized: `pieces_set`, `bitboards` and `pieces_key` are rebuilt
+/// from the `positions` when deserializing
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(try_from = "BoardRepr")]
 pub struct Board {
     /// `Board`` max `x` and `y` positions (board size)
     pub dimension: Position,
-    #[serde(skip)]
     /// `Postion`` in the Board of still playing `Piece`s
-    pub positions: HashMap<Position, Box<dyn Piece>>,
+    #[serde(serialize_with = "serialize_positions")]
+    pub positions: HashMap<Position, ChessPiece>,
     /// Set of still playing pieces `Position`s by `Color`
-    pub pieces_set: HashMap<Color, HashSet<Position>>,
     #[serde(skip)]
+    pub pieces_set: HashMap<Color, HashSet<Position>>,
     /// Set of pieces that have been already killed
-    pub pieces_dead: HashMap<Color, Vec<Box<dyn Piece>>>,
+    pub pieces_dead: HashMap<Color, Vec<ChessPiece>>,
+    /// Castling availability by `Color`
+    pub castling_rights: HashMap<Color, CastlingRights>,
+    /// Initial files of the `King` and castling `Rook`s
+    pub castling_files: CastlingFiles,
+    /// Whether castling is encoded as the `King` capturing its own `Rook`, as in Chess960,
+    /// instead of moving the `King` to its destination
+    #[serde(default)]
+    pub chess960: bool,
+    /// Square skipped by a `Pawn` double step in the last `Movement`, where it can be captured
+    /// en passant
+    pub en_passant: Option<Position>,
+    /// `Pawn` rules of the `Layout`
+    #[serde(default)]
+    pub pawn_rules: PawnRules,
+    /// Bitboard backend of the `positions`, only for 8x8 boards
+    #[serde(skip)]
+    bitboards: Option<Bitboards>,
+    /// Zobrist key of the pieces, updated as they are put and removed
+    #[serde(skip)]
+    pieces_key: u64,
+}
+
+/// Serialized `Board`, without the fields derived from its `positions`
+#[derive(Deserialize)]
+struct BoardRepr {
+    dimension: Position,
+    positions: Vec<(Position, ChessPiece)>,
+    pieces_dead: HashMap<Color, Vec<ChessPiece>>,
+    castling_rights: HashMap<Color, CastlingRights>,
+    #[serde(default)]
+    castling_files: Option<CastlingFiles>,
+    #[serde(default)]
+    chess960: bool,
+    en_passant: Option<Position>,
+    #[serde(default)]
+    pawn_rules: PawnRules,
+}
+
+impl TryFrom<BoardRepr> for Board {
+    type Error = MovementError;
+
+    /// Rebuild the `Board` putting its pieces one by one, so the derived fields agree with them
+    fn try_from(repr: BoardRepr) -> Result<Self, Self::Error> {
+        let mut board = Board::try_new(repr.dimension, None)?;
+        board.add_pieces(
+            repr.positions
+                .into_iter()
+                .map(|(position, piece)| (position, piece.to_piece()))
+                .collect(),
+        )?;
+        board.pieces_dead.extend(repr.pieces_dead);
+        board.castling_rights.extend(repr.castling_rights);
+        if let Some(files) = repr.castling_files {
+            board.castling_files = files;
+        }
+        board.chess960 = repr.chess960;
+        board.en_passant = repr.en_passant;
+        board.pawn_rules = repr.pawn_rules;
+        Ok(board)
+    }
+}
+
+impl Hash for Board {
+    /// Hash the position: dimension, pieces, castling rights and en passant square
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dimension.hash(state);
+        let mut pieces: Vec<(&Position, &ChessPiece)> = self.positions.iter().collect();
+        pieces.sort_by_key(|(position, _)| (position.x, position.y));
+        pieces.hash(state);
+        self.castling_rights(White).hash(state);
+        self.castling_rights(Black).hash(state);
+        self.castling_files.hash(state);
+        self.chess960.hash(state);
+        self.en_passant.hash(state);
+    }
 }
 
 impl Board {
     /// Creates a new `Board` which might be already initialized with some `Piece` in place
     /// `Board` dimentsion is configurable
+    ///
+    /// Panics if the dimension or the pieces are not valid, see `try_new`
     pub fn new(dimension: Position, maybe_pieces: Option<Vec<(Position, Box<dyn Piece>)>>) -> Self {
+        Self::try_new(dimension, maybe_pieces).expect("Dimension and pieces are valid")
+    }
+
+    /// Creates a new `Board` which might be already initialized with some `Piece` in place
+    ///
+    /// Can return `Err` if the dimension has no squares, more than `MAX_FILES` files or more
+    /// than `MAX_RANKS` ranks, or if the pieces can not be added, see `add_pieces`
+    pub fn try_new(
+        dimension: Position,
+        maybe_pieces: Option<Vec<(Position, Box<dyn Piece>)>>,
+    ) -> Result<Self, MovementError> {
+        ensure!(
+            (0..MAX_FILES).contains(&dimension.x) && (0..MAX_RANKS).contains(&dimension.y),
+            MovementError::OutOfBounds
+        );
         let pieces = maybe_pieces.unwrap_or(vec![]);
 
-        let mut positions = HashMap::new();
         let mut pieces_set = HashMap::new();
         let mut pieces_dead = HashMap::new();
-
-        // TODO: Check pieces_set are not out of bounds
+        let mut castling_rights = HashMap::new();
 
         // Initialize `pieces_set` and `piece_dead` in case either `White` and `Black` do not exist
-        if pieces_set.get(&White).is_none() {
-            pieces_set.insert(White, HashSet::new());
-        }
-        if pieces_set.get(&Black).is_none() {
-            pieces_set.insert(Black, HashSet::new());
+        for color in [White, Black] {
+            pieces_set.entry(color).or_default();
+            pieces_dead.entry(color).or_default();
+            castling_rights.insert(color, CastlingRights::default());
         }
-        if pieces_dead.get(&White).is_none() {
-            pieces_dead.insert(White, Vec::new());
-        }
-        if pieces_dead.get(&Black).is_none() {
-            pieces_dead.insert(Black, Vec::new());
-        }
-
-        Self::do_add_pieces(&mut positions, &mut pieces_set, pieces);
 
-        Board {
+        let mut board = Board {
             dimension,
-            positions,
+            positions: HashMap::new(),
             pieces_set,
             pieces_dead,
-        }
+            castling_rights,
+            castling_files: CastlingFiles::corners(default_king_file(dimension), dimension),
+            chess960: false,
+            en_passant: None,
+            pawn_rules: PawnRules::default(),
+            bitboards: None,
+            pieces_key: 0,
+        };
+        board.use_bitboards(true);
+        board.add_pieces(pieces)?;
+        Ok(board)
     }
 
     /// Add pieces to the `Board`
-    pub fn add_pieces(&mut self, new_pieces: Vec<(Position, Box<dyn Piece>)>) {
-        Self::do_add_pieces(&mut self.positions, &mut self.pieces_set, new_pieces);
-    }
-
-    /// Private helper method to add pieces to the `Board`
-    fn do_add_pieces(
-        positions: &mut HashMap<Position, Box<dyn Piece>>,
-        pieces_set: &mut HashMap<Color, HashSet<Position>>,
+    ///
+    /// Can return `Err` if any of them is out of bounds, or in an occupied square or in the same
+    /// square as another one, in which case none is added
+    pub fn add_pieces(
+        &mut self,
         new_pieces: Vec<(Position, Box<dyn Piece>)>,
-    ) {
-        // TODO: Check new_pieces are not out of bounds
-        // TODO: Check a piece does not exist already in that Position
+    ) -> Result<(), MovementError> {
+        let mut squares = HashSet::new();
+        for (position, _) in &new_pieces {
+            ensure!(self.in_bounds(*position), MovementError::OutOfBounds);
+            ensure!(
+                !self.positions.contains_key(position) && squares.insert(*position),
+                MovementError::Occupied(*position)
+            );
+        }
         for (position, piece) in new_pieces {
-            let piece_color = piece.color();
-            if let Some(color) = pieces_set.get_mut(&piece_color) {
-                color.insert(position);
-            } else {
-                pieces_set.insert(piece_color, HashSet::new());
-                pieces_set
-                    .get_mut(&piece_color)
-                    .expect("Color exists")
-                    .insert(position);
+            self.put_piece(position, ChessPiece::from(piece.as_ref()));
+        }
+        Ok(())
+    }
+
+    /// Enable or disable the bitboard backend used to find attacks and movements. It is only
+    /// available for 8x8 boards without `Custom` pieces, where it is enabled by default
+    pub fn use_bitboards(&mut self, enabled: bool) {
+        self.bitboards = None;
+        let custom = self
+            .positions
+            .values()
+            .any(|piece| matches!(piece.kind, Custom { .. }));
+        if enabled && !custom && self.dimension == (Position { x: 7, y: 7 }) {
+            let mut bitboards = Bitboards::default();
+            for (position, piece) in &self.positions {
+                bitboards.put(*position, *piece);
             }
-            positions.insert(position, piece);
+            self.bitboards = Some(bitboards);
         }
     }
 
@@ -175,8 +455,333 @@ impl Board {
         playing_color: Color,
         movement: &Movement,
     ) -> Result<(), MovementError> {
-        self.can_move(playing_color, &movement)
-            .and_then(|_| self.replace_square(movement))
+        self.play_movement(playing_color, movement).map(|_| ())
+    }
+
+    /// Move a piece like `move_piece`, returning the `MovementRecord` to revert it
+    pub fn play_movement(
+        &mut self,
+        playing_color: Color,
+        movement: &Movement,
+    ) -> Result<MovementRecord, MovementError> {
+        self.can_move(playing_color, movement)?;
+        let record = self.replace_square(movement)?;
+
+        // A `Movement` can not leave the own `King` in check
+        if self.is_in_check(playing_color) {
+            self.revert_square(record)?;
+            fail!(MovementError::Check);
+        }
+        Ok(record)
+    }
+
+    /// Revert the `Movement` of a `MovementRecord`, which must be the last one played
+    pub fn revert_movement(&mut self, record: MovementRecord) -> Result<(), MovementError> {
+        self.revert_square(record)
+    }
+
+    /// Returns `true` if the `Movement` is allowed and does not leave the own `King` in check
+    ///
+    /// The `Board` is left untouched
+    pub(crate) fn is_legal_move(&mut self, playing_color: Color, movement: &Movement) -> bool {
+        self.check_movement(playing_color, movement).is_ok()
+    }
+
+    /// Check the `Movement` is allowed and does not leave the own `King` in check, returning the
+    /// same error as `play_movement` otherwise
+    ///
+    /// The `Board` is left untouched
+    pub(crate) fn check_movement(
+        &mut self,
+        playing_color: Color,
+        movement: &Movement,
+    ) -> Result<(), MovementError> {
+        self.can_move(playing_color, movement)?;
+        ensure!(
+            self.keeps_king_safe(playing_color, movement),
+            MovementError::Check
+        );
+        Ok(())
+    }
+
+    /// Returns `true` if a `Movement` of `candidate_moves` is legal. Those generated from the
+    /// bitboards already follow the movement rules, so only castles are played on the `Board`:
+    /// the rest are played on a copy of the bitboards to check the `King` safety
+    fn is_legal_candidate(&mut self, playing_color: Color, movement: &Movement) -> bool {
+        let (Some(bitboards), Some(piece)) = (&self.bitboards, self.pick_piece(movement.from))
+        else {
+            return self.is_legal_move(playing_color, movement);
+        };
+        if self.is_castle(movement) {
+            return self.is_legal_move(playing_color, movement);
+        }
+        let captured = self.en_passant_capture(movement).unwrap_or(movement.to);
+        let captured = self
+            .pick_piece(captured)
+            .map(|captured_piece| (captured, captured_piece));
+        bitboards.keeps_king_safe(movement.from, movement.to, piece, captured)
+    }
+
+    /// Returns `true` if the `Movement` does not leave the own `King` in check
+    fn keeps_king_safe(&mut self, playing_color: Color, movement: &Movement) -> bool {
+        let Ok(record) = self.replace_square(movement) else {
+            return false;
+        };
+        let legal = !self.is_in_check(playing_color);
+        self.revert_square(record)
+            .expect("Movement was just applied");
+        legal
+    }
+
+    /// Applies a `Movement`, runs `f` on the resulting `Board` and reverts it
+    pub(crate) fn with_movement<T>(
+        &mut self,
+        movement: &Movement,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> Result<T, MovementError> {
+        let record = self.replace_square(movement)?;
+        let result = f(self);
+        self.revert_square(record)?;
+        Ok(result)
+    }
+
+    /// Returns `true` if a certain `Color` has at least one legal `Movement`
+    pub fn has_legal_moves(&mut self, color: Color) -> bool {
+        self.origins(color).into_iter().any(|from| {
+            self.candidate_moves(from)
+                .iter()
+                .any(|movement| self.is_legal_candidate(color, movement))
+        })
+    }
+
+    /// Returns every legal `Movement` of a certain `Color`, including castles, en passant
+    /// captures and one `Movement` per possible promotion
+    pub fn legal_moves(&mut self, color: Color) -> Vec<Movement> {
+        self.origins(color)
+            .into_iter()
+            .flat_map(|from| self.legal_moves_from(from))
+            .collect()
+    }
+
+    /// Returns every legal `Movement` of the `Piece` in a `Position`, regardless of whose turn
+    /// it is. It is empty if the square is empty
+    pub fn legal_moves_from(&mut self, from: Position) -> Vec<Movement> {
+        let Some(color) = self.pick_piece(from).map(|piece| piece.color) else {
+            return vec![];
+        };
+        self.candidate_moves(from)
+            .into_iter()
+            .filter(|movement| self.is_legal_candidate(color, movement))
+            .collect()
+    }
+
+    /// Returns the `Position`s of the pieces of a certain `Color`
+    fn origins(&self, color: Color) -> Vec<Position> {
+        if let Some(bitboards) = &self.bitboards {
+            return bitboard::positions(bitboards.color(color)).collect();
+        }
+        self.pieces_set
+            .get(&color)
+            .expect("Color exists")
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Returns the `Movement`s allowed by the movement pattern of the `Piece` in a `Position`,
+    /// which still have to be validated against the rest of the rules
+    fn candidate_moves(&self, from: Position) -> Vec<Movement> {
+        let Some(piece) = self.pick_piece(from) else {
+            return vec![];
+        };
+        let targets = match &self.bitboards {
+            Some(bitboards) if piece.kind == Pawn => {
+                let double_step =
+                    self.pawn_rules.double_step && from.y == self.pawn_row(piece.color);
+                let moves = bitboards.pawn_moves(from, piece.color, double_step, self.en_passant);
+                bitboard::positions(moves).collect()
+            }
+            Some(bitboards) => {
+                let attacks = bitboards.attacks(from, piece) & !bitboards.color(piece.color);
+                let mut targets: Vec<Position> = bitboard::positions(attacks).collect();
+                if piece.kind == King {
+                    targets.extend(self.castle_targets(from, piece.color));
+                }
+                targets
+            }
+            None => self.pattern_targets(from, piece),
+        };
+
+        targets
+            .into_iter()
+            .flat_map(|to| {
+                let movement = Movement {
+                    from,
+                    to,
+                    promotion: None,
+                };
+                if self.is_promotion(&movement) {
+                    self.pawn_rules
+                        .promotions()
+                        .into_iter()
+                        .map(|promotion| Movement {
+                            promotion: Some(promotion),
+                            ..movement
+                        })
+                        .collect()
+                } else {
+                    vec![movement]
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the destinations of the castles of a `King` in a `Position`: the `King`
+    /// destinations, or the squares of its own castling `Rook`s in Chess960
+    fn castle_targets(&self, from: Position, color: Color) -> Vec<Position> {
+        if from != self.king_square(color) {
+            return vec![];
+        }
+        [true, false]
+            .into_iter()
+            .filter_map(|king_side| {
+                if self.chess960 {
+                    let rook = self.castling_rook_square(color, king_side);
+                    (self.pick_piece(rook) == Some(ChessPiece::new(Rook, color))).then_some(rook)
+                } else {
+                    let (king, _) = self.castling_destinations(from, king_side);
+                    (king.x.abs_diff(from.x) >= 2 && self.in_bounds(king)).then_some(king)
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the squares a `Piece` in a `Position` could move to following its movement
+    /// pattern, walking the `Board` square by square
+    fn pattern_targets(&self, from: Position, piece: ChessPiece) -> Vec<Position> {
+        let straight = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+        let diagonal = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+        let knight = [
+            (1, 2),
+            (2, 1),
+            (2, -1),
+            (1, -2),
+            (-1, -2),
+            (-2, -1),
+            (-2, 1),
+            (-1, 2),
+        ];
+
+        match piece.kind {
+            King => {
+                let mut targets = self.steps(from, &[straight, diagonal].concat());
+                targets.extend(self.castle_targets(from, piece.color));
+                targets
+            }
+            Queen => self.rays(from, &[straight, diagonal].concat()),
+            Rook => self.rays(from, &straight),
+            Bishop => self.rays(from, &diagonal),
+            Knight => self.steps(from, &knight),
+            Archbishop => [self.rays(from, &diagonal), self.steps(from, &knight)].concat(),
+            Chancellor => [self.rays(from, &straight), self.steps(from, &knight)].concat(),
+            Custom { moves, .. } => {
+                let mut targets = Vec::new();
+                if moves.king {
+                    targets.extend(self.steps(from, &[straight, diagonal].concat()));
+                }
+                if moves.rook {
+                    targets.extend(self.rays(from, &straight));
+                }
+                if moves.bishop {
+                    targets.extend(self.rays(from, &diagonal));
+                }
+                if moves.knight {
+                    targets.extend(self.steps(from, &knight));
+                }
+                targets
+            }
+            Pawn => {
+                let forward = if piece.color == White { 1 } else { -1 };
+                self.steps(
+                    from,
+                    &[(0, forward), (0, 2 * forward), (1, forward), (-1, forward)],
+                )
+            }
+        }
+    }
+
+    /// Returns the in-bounds squares at a certain `(x, y)` offset from a `Position`
+    fn steps(&self, from: Position, offsets: &[(i32, i32)]) -> Vec<Position> {
+        offsets
+            .iter()
+            .map(|(x, y)| Position {
+                x: from.x + x,
+                y: from.y + y,
+            })
+            .filter(|position| self.in_bounds(*position))
+            .collect()
+    }
+
+    /// Returns the squares reachable sliding from a `Position` in some directions, stopping at
+    /// (and including) the first occupied square
+    fn rays(&self, from: Position, directions: &[(i32, i32)]) -> Vec<Position> {
+        let mut targets = Vec::new();
+        for (x, y) in directions {
+            let mut position = Position {
+                x: from.x + x,
+                y: from.y + y,
+            };
+            while self.in_bounds(position) {
+                targets.push(position);
+                if !self.square_is_empty(position) {
+                    break;
+                }
+                position.x += x;
+                position.y += y;
+            }
+        }
+        targets
+    }
+
+    /// Returns `true` if a `Position` is inside the `Board`
+    fn in_bounds(&self, position: Position) -> bool {
+        (0..=self.dimension.x).contains(&position.x) && (0..=self.dimension.y).contains(&position.y)
+    }
+
+    /// Returns `true` if no sequence of legal `Movement`s can lead to a checkmate: only kings
+    /// remain, plus either a single `Bishop` or `Knight` or any number of bishops all of them
+    /// on squares of the same color
+    pub fn has_insufficient_material(&self) -> bool {
+        let pieces: Vec<(&Position, &ChessPiece)> = self
+            .positions
+            .iter()
+            .filter(|(_, piece)| piece.kind != King)
+            .collect();
+
+        match pieces.as_slice() {
+            [] => true,
+            [(_, piece)] => matches!(piece.kind, Bishop | Knight),
+            [(first, _), ..] => pieces.iter().all(|(position, piece)| {
+                piece.kind == Bishop && (position.x + position.y) % 2 == (first.x + first.y) % 2
+            }),
+        }
+    }
+
+    /// Returns `true` if the `King` of a certain `Color` is attacked
+    pub fn is_in_check(&self, color: Color) -> bool {
+        if let Some(bitboards) = &self.bitboards {
+            return bitboard::positions(bitboards.pieces(King, color))
+                .any(|position| bitboards.is_attacked(position, !color));
+        }
+        self.pieces_set
+            .get(&color)
+            .expect("Color exists")
+            .iter()
+            .filter(|position| {
+                self.pick_piece(**position)
+                    .is_some_and(|piece| piece.kind == King)
+            })
+            .any(|position| self.is_attacked(*position, !color))
     }
 
     /// Reset the `Board` with no pieces
@@ -189,7 +794,13 @@ impl Board {
                 self.pieces_set.insert(*color, HashSet::new());
             }
             self.pieces_dead.insert(*color, Vec::new());
+            self.castling_rights
+                .insert(*color, CastlingRights::default());
         }
+        self.castling_files =
+            CastlingFiles::corners(default_king_file(self.dimension), self.dimension);
+        self.chess960 = false;
+        self.en_passant = None;
         for position in positions_to_remove {
             self.remove_piece(&position)?;
         }
@@ -202,158 +813,355 @@ impl Board {
         let piece = self
             .pick_piece(movement.from)
             .ok_or(MovementError::NoPiece)?;
-        let piece_color = piece.color();
-        let movement_kind = self.movement_kind(playing_color, &movement)?;
+        let piece_color = piece.color;
+        let movement_kind = self.movement_kind(playing_color, movement)?;
 
         // Check if piece's color intented to be moved matches with color's turn
         ensure!(
             piece_color == playing_color,
             MovementError::WrongPiece(piece_color)
         );
+        // Check if valid Castle movement
+        if self.is_castle(movement) {
+            ensure!(
+                movement.promotion.is_none(),
+                MovementError::InvalidPromotion
+            );
+            return self.can_castle(playing_color, movement);
+        }
         // Check if the movement is valid for that piece
         self.is_valid_move(piece, &movement_kind)?;
         // Check it the movement target id valid
         ensure!(
-            self.valid_target(playing_color, &movement),
+            self.valid_target(playing_color, movement),
             MovementError::BlockedPath
         );
         // Check if the movement path is blocked
-        let blocked_path = self.blocked_path(&movement, &movement_kind, &playing_color);
-        if !piece.is_king() && !piece.is_knight() {
-            ensure!(!blocked_path, MovementError::BlockedPath);
+        ensure!(
+            !self.blocked_path(movement, &movement_kind),
+            MovementError::BlockedPath
+        );
+        // Check pawn special movements
+        if piece.kind == Pawn {
+            self.can_move_pawn(playing_color, movement, &movement_kind)?;
         }
-        // Check if valid Castle movement
-        if piece.is_king() && blocked_path {
-            // TODO: Check if valid Castle movement
+        // Check a promotion is set only, and always, when a `Pawn` reaches the last row, to a
+        // piece allowed by the `PawnRules`
+        ensure!(
+            self.is_promotion(movement) == movement.promotion.is_some()
+                && movement
+                    .promotion
+                    .is_none_or(|promotion| self.pawn_rules.promotions().contains(&promotion)),
+            MovementError::InvalidPromotion
+        );
+        Ok(true)
+    }
+
+    /// Check `Pawn` special rules:
+    /// - `Forward(2)` is only allowed from its starting row, if the `PawnRules` allow it
+    /// - Forward movements can not capture
+    /// - Diagonal movements must capture, either a rival piece or en passant
+    fn can_move_pawn(
+        &self,
+        playing_color: Color,
+        movement: &Movement,
+        movement_kind: &MovementKind,
+    ) -> Result<bool, MovementError> {
+        match movement_kind {
+            Vertical(direction) => {
+                ensure!(
+                    *direction != Forward(2)
+                        || self.pawn_rules.double_step
+                            && movement.from.y == self.pawn_row(playing_color),
+                    MovementError::IllegalMovement
+                );
+                ensure!(
+                    self.square_is_empty(movement.to),
+                    MovementError::BlockedPath
+                );
+            }
+            Diagonal(_) => {
+                ensure!(
+                    !self.square_is_empty(movement.to) || self.en_passant == Some(movement.to),
+                    MovementError::IllegalMovement
+                );
+            }
+            _ => fail!(MovementError::IllegalMovement),
         }
-        // Check pawn special movements
-        if piece.is_pawn() {
-            // TODO: Check pawn special movements
-            // - Forward(2) when `moved = false`
-            // - kill piece
-            // - special movement
+        Ok(true)
+    }
+
+    /// Returns `true` if the `Movement` takes a `Pawn` to the last row
+    fn is_promotion(&self, movement: &Movement) -> bool {
+        self.pick_piece(movement.from)
+            .is_some_and(|piece| piece.kind == Pawn && movement.to.y == self.home_row(!piece.color))
+    }
+
+    /// Returns `true` if the `Movement` has the shape of a castle: a `King` leaving its
+    /// initial square to its castling destination, or capturing its own castling `Rook` in
+    /// Chess960
+    pub(crate) fn is_castle(&self, movement: &Movement) -> bool {
+        let Some(piece) = self.pick_piece(movement.from) else {
+            return false;
+        };
+        if piece.kind != King
+            || movement.from != self.king_square(piece.color)
+            || movement.to.y != movement.from.y
+        {
+            return false;
         }
+        let king_side = movement.to.x > movement.from.x;
+        if self.chess960 {
+            movement.to == self.castling_rook_square(piece.color, king_side)
+                && self.pick_piece(movement.to) == Some(ChessPiece::new(Rook, piece.color))
+        } else {
+            movement.to.x.abs_diff(movement.from.x) >= 2
+                && movement.to == self.castling_destinations(movement.from, king_side).0
+        }
+    }
+
+    /// Check if a castle `Movement` is valid:
+    /// - The castling side has not been lost (neither the `King` nor the `Rook` have moved)
+    /// - There is a `Rook` of the same `Color` in its initial square
+    /// - Squares crossed by the `King` and the `Rook` are empty, but for themselves
+    /// - `King` is not in check, does not cross an attacked square and does not end in check
+    fn can_castle(&self, playing_color: Color, movement: &Movement) -> Result<bool, MovementError> {
+        let king_side = movement.to.x > movement.from.x;
+        let rights = self.castling_rights(playing_color);
+        ensure!(
+            if king_side {
+                rights.king_side
+            } else {
+                rights.queen_side
+            },
+            MovementError::IllegalMovement
+        );
+
+        let rook_position = self.castling_rook_square(playing_color, king_side);
+        ensure!(
+            self.pick_piece(rook_position)
+                .is_some_and(|rook| rook == ChessPiece::new(Rook, playing_color)),
+            MovementError::IllegalMovement
+        );
+        let (king_destination, rook_destination) =
+            self.castling_destinations(movement.from, king_side);
+        let files = [
+            movement.from.x,
+            king_destination.x,
+            rook_position.x,
+            rook_destination.x,
+        ];
+        let row = |from: i32, to: i32| {
+            (from.min(to)..=from.max(to)).map(move |x| Position {
+                x,
+                y: movement.from.y,
+            })
+        };
+        let first = *files.iter().min().expect("Files exist");
+        let last = *files.iter().max().expect("Files exist");
+        ensure!(
+            row(first, last).all(|position| {
+                position == movement.from
+                    || position == rook_position
+                    || self.square_is_empty(position)
+            }),
+            MovementError::BlockedPath
+        );
+        ensure!(
+            row(movement.from.x, king_destination.x)
+                .all(|position| !self.is_attacked(position, !playing_color)),
+            MovementError::Check
+        );
         Ok(true)
     }
 
+    /// Returns where the `King` and the `Rook` land when castling on one side: the files next
+    /// to the corner, `g` and `f` (`c` and `d`) on 8x8 boards
+    pub(crate) fn castling_destinations(
+        &self,
+        king: Position,
+        king_side: bool,
+    ) -> (Position, Position) {
+        let (king_x, rook_x) = if king_side {
+            (self.dimension.x - 1, self.dimension.x - 2)
+        } else {
+            (2, 3)
+        };
+        (
+            Position {
+                x: king_x,
+                y: king.y,
+            },
+            Position {
+                x: rook_x,
+                y: king.y,
+            },
+        )
+    }
+
+    /// Returns the `CastlingRights` of a `Color`
+    pub fn castling_rights(&self, color: Color) -> CastlingRights {
+        self.castling_rights
+            .get(&color)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if the `King` and the `Rook` of a castling side are in their initial
+    /// squares, regardless of the `CastlingRights`
+    pub fn can_castle_side(&self, color: Color, king_side: bool) -> bool {
+        let king = self.pick_piece(self.king_square(color));
+        let rook = self.pick_piece(self.castling_rook_square(color, king_side));
+
+        king == Some(ChessPiece::new(King, color)) && rook == Some(ChessPiece::new(Rook, color))
+    }
+
+    /// Returns the initial square of the `King` of a certain `Color`
+    fn king_square(&self, color: Color) -> Position {
+        Position {
+            x: self.castling_files.king,
+            y: self.home_row(color),
+        }
+    }
+
+    /// Returns the initial square of the `Rook` used to castle on one side
+    pub(crate) fn castling_rook_square(&self, color: Color, king_side: bool) -> Position {
+        let x = if king_side {
+            self.castling_files.king_side_rook
+        } else {
+            self.castling_files.queen_side_rook
+        };
+        Position {
+            x,
+            y: self.home_row(color),
+        }
+    }
+
+    /// Returns the row where the pawns of a `Color` start
+    fn pawn_row(&self, color: Color) -> i32 {
+        match color {
+            White => self.home_row(color) + 1,
+            Black => self.home_row(color) - 1,
+        }
+    }
+
+    /// Returns the row where the pieces of a `Color` start
+    fn home_row(&self, color: Color) -> i32 {
+        match color {
+            White => 0,
+            Black => self.dimension.y,
+        }
+    }
+
+    /// Returns `true` if any `Piece` of `attacker` color could capture in `position`
+    pub fn is_attacked(&self, position: Position, attacker: Color) -> bool {
+        if let Some(bitboards) = &self.bitboards {
+            return self.in_bounds(position) && bitboards.is_attacked(position, attacker);
+        }
+        self.pieces_set
+            .get(&attacker)
+            .expect("Color exists")
+            .iter()
+            .any(|from| {
+                let movement = Movement {
+                    from: *from,
+                    to: position,
+                    promotion: None,
+                };
+                let piece = self.pick_piece(*from).expect("Piece exists");
+                let Ok(movement_kind) = self.movement_kind(attacker, &movement) else {
+                    return false;
+                };
+                if piece.kind == Pawn {
+                    return matches!(movement_kind, Diagonal((Forward(1), _)));
+                }
+                self.is_valid_move(piece, &movement_kind).is_ok()
+                    && !self.blocked_path(&movement, &movement_kind)
+            })
+    }
+
     /// Returns a set of already killed pieces for a certain `Color`
-    pub fn dead_pieces(&self, color: Color) -> &Vec<Box<dyn Piece>> {
-        self.pieces_dead.get(&color).expect("Color exists")
+    pub fn dead_pieces(&self, color: Color) -> Vec<Box<dyn Piece>> {
+        self.pieces_dead
+            .get(&color)
+            .expect("Color exists")
+            .iter()
+            .map(|piece| piece.to_piece())
+            .collect()
     }
 
     /// Check a `Movement` is valid for a certain `Piece` type
     fn is_valid_move(
         &self,
-        piece: &Box<dyn Piece>,
+        piece: ChessPiece,
         movement_kind: &MovementKind,
     ) -> Result<bool, MovementError> {
-        let valid_moves = if piece.is_king() {
-            vec![
-                Vertical(Forward(1)),
-                Vertical(Backward(1)),
-                Horizontal(Left(1)),
-                Horizontal(Right(1)),
-                Diagonal((Forward(1), Right(1))),
-                Diagonal((Forward(1), Left(1))),
-                Diagonal((Backward(1), Right(1))),
-                Diagonal((Backward(1), Left(1))),
-            ]
-        } else if piece.is_queen() {
-            self.build_valid_moves(
-                |y| vec![Vertical(Forward(y)), Vertical(Backward(y))],
-                |x| vec![Horizontal(Left(x)), Horizontal(Right(x))],
-                |z| {
-                    vec![
-                        Diagonal((Forward(z), Right(z))),
-                        Diagonal((Forward(z), Left(z))),
-                        Diagonal((Backward(z), Right(z))),
-                        Diagonal((Backward(z), Left(z))),
-                    ]
-                },
-            )
-        } else if piece.is_rook() {
-            self.build_valid_moves(
-                |y| vec![Vertical(Forward(y)), Vertical(Backward(y))],
-                |x| vec![Horizontal(Left(x)), Horizontal(Right(x))],
-                |_z| vec![],
-            )
-        } else if piece.is_knight() {
-            vec![KnightMovement]
-        } else if piece.is_bishop() {
-            self.build_valid_moves(
-                |_y| vec![],
-                |_x| vec![],
-                |z| {
-                    vec![
-                        Diagonal((Forward(z), Right(z))),
-                        Diagonal((Forward(z), Left(z))),
-                        Diagonal((Backward(z), Right(z))),
-                        Diagonal((Backward(z), Left(z))),
-                    ]
-                },
-            )
-        } else if piece.is_pawn() {
-            vec![
-                Vertical(Forward(1)),
-                Vertical(Forward(2)),
-                Diagonal((Forward(1), Left(1))),
-                Diagonal((Forward(1), Right(1))),
-            ]
-        } else {
-            vec![]
+        let moves = piece.kind.moves();
+        let valid = match movement_kind {
+            _ if piece.kind == Pawn => matches!(
+                movement_kind,
+                Vertical(Forward(1) | Forward(2)) | Diagonal((Forward(1), Left(1) | Right(1)))
+            ),
+            Vertical(Forward(1) | Backward(1))
+            | Horizontal(Left(1) | Right(1))
+            | Diagonal((Forward(1) | Backward(1), Left(1) | Right(1)))
+                if moves.king =>
+            {
+                true
+            }
+            Vertical(_) | Horizontal(_) => moves.rook,
+            Diagonal(_) => moves.bishop,
+            KnightMovement => moves.knight,
         };
-        ensure!(
-            valid_moves.iter().any(|m| m == movement_kind),
-            MovementError::IllegalMovement
-        );
+        ensure!(valid, MovementError::IllegalMovement);
         Ok(true)
     }
 
-    /// Helper method to build all possible `MovementKind` for a certain `Piece` type
-    /// based of `Board` dimensions
-    fn build_valid_moves<Y, X, Z>(&self, fy: Y, fx: X, fz: Z) -> Vec<MovementKind>
-    where
-        Y: Fn(u32) -> Vec<MovementKind>,
-        X: Fn(u32) -> Vec<MovementKind>,
-        Z: Fn(u32) -> Vec<MovementKind>,
-    {
-        let max_y = self.dimension.y as u32;
-        let max_x = self.dimension.x as u32;
-        let min = max_x.min(max_y);
-
-        vec![
-            (1..=max_y)
-                .into_iter()
-                .map(|y| fy(y))
-                .flatten()
-                .collect::<Vec<MovementKind>>(),
-            (1..=max_x).into_iter().map(|x| fx(x)).flatten().collect(),
-            (1..=min).into_iter().map(|z| fz(z)).flatten().collect(),
-        ]
-        .into_iter()
-        .flatten()
-        .collect()
+    /// Return a `Piece` in case it existis in a `Position`
+    fn pick_piece(&self, position: Position) -> Option<ChessPiece> {
+        self.positions.get(&position).copied()
     }
 
-    /// Return a `Piece` in case it existis in a `Position`
-    fn pick_piece(&self, position: Position) -> Option<&Box<dyn Piece>> {
-        self.positions.get(&position)
+    /// Add a single `ChessPiece` to the `Board`, in an empty square: `add_pieces` checks it, and
+    /// movements empty their target first
+    fn put_piece(&mut self, position: Position, piece: ChessPiece) {
+        self.pieces_set
+            .entry(piece.color)
+            .or_default()
+            .insert(position);
+        self.positions.insert(position, piece);
+        if matches!(piece.kind, Custom { .. }) {
+            self.bitboards = None;
+        }
+        if let Some(bitboards) = &mut self.bitboards {
+            bitboards.put(position, piece);
+        }
+        self.pieces_key ^= zobrist::piece_key(position, piece);
     }
 
     /// Remove (kill) a piece in a `Position`
     /// Can return an `Err` if the square is empty
-    pub fn remove_piece(&mut self, position: &Position) -> Result<Box<dyn Piece>, MovementError> {
+    pub fn remove_piece(&mut self, position: &Position) -> Result<ChessPiece, MovementError> {
         let piece_origin = self
             .positions
-            .remove(&position)
+            .remove(position)
             .ok_or(MovementError::NoPiece)?;
         self.pieces_set
-            .get_mut(&piece_origin.color())
+            .get_mut(&piece_origin.color)
             .expect("Color exists")
-            .remove(&position);
+            .remove(position);
+        if let Some(bitboards) = &mut self.bitboards {
+            bitboards.remove(*position, piece_origin);
+        }
+        self.pieces_key ^= zobrist::piece_key(*position, piece_origin);
         Ok(piece_origin)
     }
 
+    /// Returns the Zobrist key of the pieces alone
+    pub(crate) fn pieces_key(&self) -> u64 {
+        self.pieces_key
+    }
+
     /// Returns `true` if a `Board` square has no `Piece`
     fn square_is_empty(&self, position: Position) -> bool {
         self.pick_piece(position).is_none()
@@ -365,79 +1173,168 @@ impl Board {
     fn valid_target(&self, playing_color: Color, movement: &Movement) -> bool {
         let target_square = self.pick_piece(movement.to);
         // Either a rival piece or empty
-        let rival_piece = target_square.is_some_and(|piece| piece.color() != playing_color);
+        let rival_piece = target_square.is_some_and(|piece| piece.color != playing_color);
         let empty_square = target_square.is_none();
 
         rival_piece || empty_square
     }
 
     /// Returns `true` if there are pieces between `Movement`'s origin and destination for a certain `MovementKind`
-    fn blocked_path(
-        &self,
-        movement: &Movement,
-        movement_kind: &MovementKind,
-        color: &Color,
-    ) -> bool {
+    fn blocked_path(&self, movement: &Movement, movement_kind: &MovementKind) -> bool {
         match movement_kind {
-            Vertical(direction) => {
-                Self::path_range(direction, movement.from.y, movement.to.y, color).any(|y| {
-                    !self.square_is_empty(Position {
-                        x: movement.from.x,
-                        y,
-                    })
-                })
-            }
-            Horizontal(direction) => {
-                Self::path_range(direction, movement.from.x, movement.to.x, color).any(|x| {
-                    !self.square_is_empty(Position {
-                        x,
-                        y: movement.from.y,
-                    })
-                })
-            }
-            Diagonal((vertical_directon, horizontal_direction)) => {
-                Self::path_range(vertical_directon, movement.from.y, movement.to.y, color)
-                    .enumerate()
-                    .any(|(i, y)| {
-                        let x = Self::path_range(
-                            horizontal_direction,
-                            movement.from.x,
-                            movement.to.x,
-                            color,
-                        )
-                        .rev()
-                        .collect::<Vec<i32>>()[i];
-                        !self.square_is_empty(Position { x, y })
-                    })
-            }
+            Vertical(_) | Horizontal(_) | Diagonal(_) => Self::path(movement.from, movement.to)
+                .any(|position| !self.square_is_empty(position)),
             _ => false,
         }
     }
 
     /// Replace `Movement`'s destination content with the existing piece in the origin
-    fn replace_square(&mut self, movement: &Movement) -> Result<(), MovementError> {
-        // Remove piece from origin and update its `pieces_set`
-        let piece_origin = self.remove_piece(&movement.from)?;
-        self.pieces_set
-            .get_mut(&piece_origin.color())
-            .expect("Color exists")
-            .insert(movement.to);
+    ///
+    /// Returns a `MovementRecord` with everything needed to revert it
+    fn replace_square(&mut self, movement: &Movement) -> Result<MovementRecord, MovementError> {
+        let mut record = MovementRecord {
+            from: movement.from,
+            to: movement.to,
+            promoted_pawn: None,
+            captured: None,
+            castle_rook: None,
+            castling_rights: [self.castling_rights(White), self.castling_rights(Black)],
+            en_passant: self.en_passant,
+        };
 
-        // Insert origin piece in target and remove killed rival piece if existed in that square
-        if let Some(killed_piece) = self.positions.insert(movement.to, piece_origin) {
-            self.pieces_set
-                .get_mut(&killed_piece.color())
+        // Move the `Rook` as well when castling. Both are lifted before landing, as in Chess960
+        // either of them may land in the other's initial square
+        if self.is_castle(movement) {
+            let king_side = movement.to.x > movement.from.x;
+            let color = self.pick_piece(movement.from).expect("King exists").color;
+            let rook_origin = self.castling_rook_square(color, king_side);
+            let (king_position, rook_position) =
+                self.castling_destinations(movement.from, king_side);
+            let king = self.remove_piece(&movement.from)?;
+            let rook = self.remove_piece(&rook_origin)?;
+            self.put_piece(king_position, king);
+            self.put_piece(rook_position, rook);
+            self.update_castling_rights(movement);
+            self.en_passant = None;
+            record.to = king_position;
+            record.castle_rook = Some((rook_origin, rook_position));
+            return Ok(record);
+        }
+        self.update_castling_rights(movement);
+
+        // Remove the captured `Pawn` when capturing en passant, which is not in the target square
+        if let Some(captured_position) = self.en_passant_capture(movement) {
+            let killed_piece = self.remove_piece(&captured_position)?;
+            record.captured = Some((captured_position, killed_piece.color));
+            self.pieces_dead
+                .get_mut(&killed_piece.color)
                 .expect("Color exists")
-                .remove(&movement.to);
+                .push(killed_piece);
+        }
+        self.en_passant = self.en_passant_target(movement);
+
+        // Remove piece from origin and update its `pieces_set`
+        let mut piece_origin = self.remove_piece(&movement.from)?;
+        if let Some(promotion) = movement.promotion {
+            record.promoted_pawn = Some(piece_origin);
+            piece_origin = ChessPiece::new(promotion.kind(), piece_origin.color);
+        }
+
+        // Remove killed rival piece if existed in the target and insert origin piece there
+        if let Ok(killed_piece) = self.remove_piece(&movement.to) {
+            record.captured = Some((movement.to, killed_piece.color));
             self.pieces_dead
-                .get_mut(&killed_piece.color())
+                .get_mut(&killed_piece.color)
                 .expect("Color exists")
                 .push(killed_piece);
         }
+        self.put_piece(movement.to, piece_origin);
+
+        Ok(record)
+    }
+
+    /// Revert a `Movement` applied by `replace_square`, leaving the `Board` as it was before
+    fn revert_square(&mut self, record: MovementRecord) -> Result<(), MovementError> {
+        let rook = match record.castle_rook {
+            Some((_, rook_position)) => Some(self.remove_piece(&rook_position)?),
+            None => None,
+        };
+        let mut piece = self.remove_piece(&record.to)?;
+        if let Some(pawn) = record.promoted_pawn {
+            piece = pawn;
+        }
+        self.put_piece(record.from, piece);
+
+        if let Some((position, color)) = record.captured {
+            let killed_piece = self
+                .pieces_dead
+                .get_mut(&color)
+                .expect("Color exists")
+                .pop()
+                .ok_or(MovementError::NoPiece)?;
+            self.put_piece(position, killed_piece);
+        }
+        if let (Some((rook_origin, _)), Some(rook)) = (record.castle_rook, rook) {
+            self.put_piece(rook_origin, rook);
+        }
+        let [white, black] = record.castling_rights;
+        self.castling_rights.insert(White, white);
+        self.castling_rights.insert(Black, black);
+        self.en_passant = record.en_passant;
 
         Ok(())
     }
 
+    /// Returns the `Position` of the `Pawn` captured en passant by a `Movement`, if any
+    fn en_passant_capture(&self, movement: &Movement) -> Option<Position> {
+        let piece = self.pick_piece(movement.from)?;
+        let is_capture = piece.kind == Pawn
+            && self.en_passant == Some(movement.to)
+            && movement.from.x != movement.to.x
+            && self.square_is_empty(movement.to);
+
+        is_capture.then_some(Position {
+            x: movement.to.x,
+            y: movement.from.y,
+        })
+    }
+
+    /// Returns the square skipped by a `Movement` if it is a `Pawn` double step
+    fn en_passant_target(&self, movement: &Movement) -> Option<Position> {
+        let piece = self.pick_piece(movement.from)?;
+        let double_step =
+            movement.from.x == movement.to.x && movement.from.y.abs_diff(movement.to.y) == 2;
+
+        (piece.kind == Pawn && double_step).then_some(Position {
+            x: movement.from.x,
+            y: (movement.from.y + movement.to.y) / 2,
+        })
+    }
+
+    /// Revoke the castling sides affected by a `Movement`, either because the `King` or a `Rook`
+    /// leave their initial square or because a `Rook` is captured in it
+    fn update_castling_rights(&mut self, movement: &Movement) {
+        for color in [White, Black] {
+            let king_square = self.king_square(color);
+            let king_side_rook = self.castling_rook_square(color, true);
+            let queen_side_rook = self.castling_rook_square(color, false);
+            let rights = self.castling_rights.entry(color).or_default();
+
+            for position in [movement.from, movement.to] {
+                if position == king_square {
+                    rights.king_side = false;
+                    rights.queen_side = false;
+                }
+                if position == king_side_rook {
+                    rights.king_side = false;
+                }
+                if position == queen_side_rook {
+                    rights.queen_side = false;
+                }
+            }
+        }
+    }
+
     /// Returns the `MovementKind` depending on `Movement` and piece `Color` (to determine the direction)
     /// Can return `Err` if the movemement is out of bounds or there is not movement (origin == destination)
     fn movement_kind(
@@ -450,8 +1347,10 @@ impl Board {
         ensure!(!no_move, MovementError::IllegalMovement);
 
         // Check it is not out of bounds
-        let out_of_bounds = movement.to.x > self.dimension.x || movement.to.y > self.dimension.y;
-        ensure!(!out_of_bounds, MovementError::OutOfBounds);
+        ensure!(
+            self.in_bounds(movement.from) && self.in_bounds(movement.to),
+            MovementError::OutOfBounds
+        );
 
         let x_variance = movement.from.x.abs_diff(movement.to.x);
         let y_variance = movement.from.y.abs_diff(movement.to.y);
@@ -544,19 +1443,32 @@ impl Board {
         }
     }
 
-    /// Returns a path range based on the `Direction`
-    fn path_range(
-        direction: &Direction,
-        from: i32,
-        to: i32,
-        color: &Color,
-    ) -> std::ops::Range<i32> {
-        match direction {
-            Forward(_) | Right(_) if *color == White => from + 1..to,
-            Forward(_) | Right(_) if *color == Black => to + 1..from,
-            Backward(_) | Left(_) if *color == White => to + 1..from,
-            Backward(_) | Left(_) if *color == Black => from + 1..to,
-            _ => from..to,
-        }
+    /// Returns the squares strictly between two `Position`s in the same row, column or diagonal
+    fn path(from: Position, to: Position) -> impl Iterator<Item = Position> {
+        let step_x = (to.x - from.x).signum();
+        let step_y = (to.y - from.y).signum();
+        let steps = from.x.abs_diff(to.x).max(from.y.abs_diff(to.y)) as i32;
+
+        (1..steps).map(move |i| Position {
+            x: from.x + i * step_x,
+            y: from.y + i * step_y,
+        })
     }
 }
+
+/// Serialize `Board::positions` as a list of `(Position, ChessPiece)`, as JSON maps only allow
+/// string keys
+fn serialize_positions<S: Serializer>(
+    positions: &HashMap<Position, ChessPiece>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut pieces: Vec<(&Position, &ChessPiece)> = positions.iter().collect();
+    pieces.sort_by_key(|(position, _)| (position.y, position.x));
+    serializer.collect_seq(pieces)
+}
+
+/// Returns the file of the `King` of a `Board` built without a `Layout`: the middle one, or the
+/// one next to it towards the king side, e.g. the `e` file on 8x8 boards
+fn default_king_file(dimension: Position) -> i32 {
+    (dimension.x + 1) / 2
+}
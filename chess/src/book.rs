@@ -0,0 +1,189 @@
+//! Book module.
+//!
+//! Read Polyglot opening books and choose book movements for a `Game`
+//!
+use super::{
+    board::{Movement, Position},
+    ensure,
+    game::Game,
+    pieces::{PieceKind, Promotion},
+    zobrist::splitmix64,
+};
+use std::{error::Error, fmt, fs, path::Path};
+
+/// Size of a Polyglot entry: key, movement, weight and learn data
+const ENTRY_SIZE: usize = 16;
+
+/// List of Errors reading a Polyglot book
+#[derive(Debug, PartialEq, Eq)]
+pub enum BookError {
+    /// The file can not be read
+    Io(String),
+    /// The size is not a multiple of the entry size
+    InvalidSize(usize),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Io(error) => write!(f, "Book can not be read: {}", error),
+            BookError::InvalidSize(size) => write!(
+                f,
+                "Book size {} is not a multiple of {} bytes",
+                size, ENTRY_SIZE
+            ),
+        }
+    }
+}
+
+impl Error for BookError {}
+
+/// Candidate movement found in a book
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct BookMove {
+    pub movement: Movement,
+    /// Relative weight, the higher the more often it is played
+    pub weight: u16,
+}
+
+/// How to choose among the candidate movements of a position
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum BookSelection {
+    /// Always the movement with the highest weight
+    Best,
+    /// A random movement with probability proportional to its weight. The same seed always
+    /// chooses the same movement
+    Weighted(u64),
+}
+
+/// Entry of a Polyglot book
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+struct BookEntry {
+    key: u64,
+    movement: u16,
+    weight: u16,
+}
+
+/// Polyglot opening book, looked up by the Zobrist hash of the position
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct PolyglotBook {
+    /// Entries sorted by key
+    entries: Vec<BookEntry>,
+}
+
+impl PolyglotBook {
+    /// Read a Polyglot `.bin` book file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BookError> {
+        let bytes = fs::read(path).map_err(|error| BookError::Io(error.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Read a Polyglot book from its content: big-endian entries of 16 bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        ensure!(
+            bytes.len().is_multiple_of(ENTRY_SIZE),
+            BookError::InvalidSize(bytes.len())
+        );
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| BookEntry {
+                key: u64::from_be_bytes(entry[0..8].try_into().expect("8 bytes")),
+                movement: u16::from_be_bytes([entry[8], entry[9]]),
+                weight: u16::from_be_bytes([entry[10], entry[11]]),
+            })
+            .collect();
+        // Books are sorted already, but a stable sort keeps their order within a key
+        entries.sort_by_key(|entry| entry.key);
+        Ok(PolyglotBook { entries })
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the book has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the legal book movements of the current position of a `Game`, in book order.
+    /// It is empty out of the book or for boards other than 8x8
+    pub fn moves(&self, game: &Game) -> Vec<BookMove> {
+        if game.board.dimension != (Position { x: 7, y: 7 }) {
+            return vec![];
+        }
+        let key = game.hash();
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let mut board = game.board.clone();
+        let legal_moves = board.legal_moves(Game::static_playing_color(game.turn));
+
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                let movement = decode_movement(game, entry.movement)?;
+                legal_moves.contains(&movement).then_some(BookMove {
+                    movement,
+                    weight: entry.weight,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns a book movement for the current position of a `Game`, or `None` out of the book
+    pub fn select(&self, game: &Game, selection: BookSelection) -> Option<Movement> {
+        let moves = self.moves(game);
+        let chosen = match selection {
+            BookSelection::Best => moves.iter().max_by_key(|book_move| book_move.weight),
+            BookSelection::Weighted(seed) => {
+                let total: u64 = moves.iter().map(|book_move| book_move.weight as u64).sum();
+                if total == 0 {
+                    moves.first()
+                } else {
+                    let mut target = splitmix64(seed ^ game.hash()) % total;
+                    moves.iter().find(|book_move| {
+                        let found = target < book_move.weight as u64;
+                        target = target.saturating_sub(book_move.weight as u64);
+                        found
+                    })
+                }
+            }
+        };
+        chosen.map(|book_move| book_move.movement)
+    }
+}
+
+/// Decode a Polyglot movement: destination in bits 0-5, origin in bits 6-11 and promotion in
+/// bits 12-14. Castling is encoded as the `King` capturing its own `Rook`, as in Chess960
+fn decode_movement(game: &Game, encoded: u16) -> Option<Movement> {
+    let square = |bits: u16| Position {
+        x: (bits & 7) as i32,
+        y: ((bits >> 3) & 7) as i32,
+    };
+    let from = square(encoded >> 6);
+    let mut to = square(encoded);
+    let promotion = match (encoded >> 12) & 7 {
+        0 => None,
+        1 => Some(Promotion::Knight),
+        2 => Some(Promotion::Bishop),
+        3 => Some(Promotion::Rook),
+        4 => Some(Promotion::Queen),
+        _ => return None,
+    };
+
+    let piece = game.board.positions.get(&from)?;
+    let own_rook = game
+        .board
+        .positions
+        .get(&to)
+        .is_some_and(|target| target.kind == PieceKind::Rook && target.color == piece.color);
+    if piece.kind == PieceKind::King && own_rook && !game.board.chess960 {
+        to = game.board.castling_destinations(from, to.x > from.x).0;
+    }
+    Some(Movement {
+        from,
+        to,
+        promotion,
+    })
+}
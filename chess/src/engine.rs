@@ -0,0 +1,540 @@
+//! Engine module.
+//!
+//! Iterative deepening alpha-beta search to find the best `Movement` of a position
+//!
+use super::{
+    board::{Board, Movement, Position},
+    game::Game,
+    pieces::{
+        ChessPiece,
+        Color::{self, White},
+        PieceKind,
+    },
+    zobrist,
+};
+use std::{
+    cmp::Reverse,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Score of a checkmate, minus the plies needed to reach it
+pub const MATE_SCORE: i32 = 100_000;
+/// Deepest search allowed, in plies
+pub const MAX_DEPTH: u32 = 64;
+/// Bound of the scores, above any mate
+const INFINITY: i32 = MATE_SCORE + 1;
+/// Movements expected until the end of the game when the time control does not tell
+const MOVES_TO_GO: u32 = 30;
+/// Time kept in the clock for the communication overhead
+const CLOCK_MARGIN: Duration = Duration::from_millis(50);
+/// Non-pawn material of both sides below which the endgame `King` table is used
+const ENDGAME_MATERIAL: i32 = 1_300;
+
+/// Piece-square tables from `White`'s point of view, the 8th rank first
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+/// Limits of a search, which stops at whichever is reached first. Without any, it runs up to
+/// `MAX_DEPTH` or until it is stopped
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct SearchLimits {
+    /// Maximum depth, in plies
+    pub depth: Option<u32>,
+    /// Time budget
+    pub time: Option<Duration>,
+}
+
+impl SearchLimits {
+    /// Returns the limits to play with a clock: the remaining time split among the movements
+    /// left to the next time control (or an estimate), plus most of the increment
+    pub fn from_clock(remaining: Duration, increment: Duration, moves_to_go: Option<u32>) -> Self {
+        let moves = moves_to_go.unwrap_or(MOVES_TO_GO).max(1);
+        let time = remaining / moves + increment * 3 / 4;
+        // Keep a safety margin to answer in time
+        let time = time.min(remaining.saturating_sub(CLOCK_MARGIN) / 2);
+        SearchLimits {
+            depth: None,
+            time: Some(time),
+        }
+    }
+}
+
+/// Result of the deepest completed iteration of a search
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SearchResult {
+    /// `None` if there are no legal movements
+    pub best_move: Option<Movement>,
+    /// Centipawns from the point of view of the `Color` to move
+    pub score: i32,
+    /// Principal variation, starting with the `best_move`
+    pub pv: Vec<Movement>,
+    /// Depth of the iteration, in plies
+    pub depth: u32,
+    /// Positions searched so far
+    pub nodes: u64,
+}
+
+impl SearchResult {
+    /// Returns the moves to checkmate if the score is a mate: positive when the `Color` to move
+    /// mates, negative when it is mated
+    pub fn mate_in(&self) -> Option<i32> {
+        let plies = MATE_SCORE - self.score.abs();
+        if plies > MAX_DEPTH as i32 {
+            return None;
+        }
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+/// Alpha-beta search engine: negamax with quiescence search, ordering the movements by the
+/// previous principal variation, MVV-LVA and killer movements
+#[derive(Debug, Default)]
+pub struct Engine {
+    /// Positions searched in the current search
+    nodes: u64,
+    /// When the current search has to stop, if it has a time budget
+    deadline: Option<Instant>,
+    /// Set from outside to stop the search
+    stop: Arc<AtomicBool>,
+    /// Whether the current iteration was stopped before completing
+    stopped: bool,
+    /// Depth of the current iteration
+    root_depth: u32,
+    /// Quiet movements which caused a beta cutoff, by ply
+    killers: Vec<[Option<Movement>; 2]>,
+    /// Principal variation of the previous iteration
+    pv: Vec<Movement>,
+    /// Hash of the positions played in the `Game` and in the current line, to detect repetitions
+    history: Vec<u64>,
+}
+
+impl Engine {
+    /// Returns a flag which stops the running search when set. The search returns the result of
+    /// the last completed iteration and clears the flag
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Search the best `Movement` of the `Color` to move in a `Game`
+    pub fn search(&mut self, game: &Game, limits: SearchLimits) -> SearchResult {
+        self.search_with(game, limits, |_| {})
+    }
+
+    /// Search like `search`, calling `on_iteration` with the result of every completed iteration
+    pub fn search_with(
+        &mut self,
+        game: &Game,
+        limits: SearchLimits,
+        mut on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let mut board = game.board.clone();
+        let color = Game::static_playing_color(game.turn);
+        self.stopped = false;
+        self.nodes = 0;
+        self.deadline = limits.time.map(|time| Instant::now() + time);
+        self.killers = vec![[None; 2]; MAX_DEPTH as usize];
+        self.pv.clear();
+        self.history = game.position_history.clone();
+        if self.history.last() != Some(&game.hash()) {
+            self.history.push(game.hash());
+        }
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            pv: Vec::new(),
+            depth: 0,
+            nodes: 0,
+        };
+        let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        for depth in 1..=max_depth {
+            self.root_depth = depth;
+            let mut pv = Vec::new();
+            let score = self.negamax(&mut board, color, depth, 0, -INFINITY, INFINITY, &mut pv);
+            // An unfinished iteration is discarded
+            if self.stopped {
+                break;
+            }
+            self.pv = pv.clone();
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                score,
+                pv,
+                depth,
+                nodes: self.nodes,
+            };
+            on_iteration(&result);
+            // Deeper iterations can not find a shorter mate
+            if result.best_move.is_none() || result.mate_in().is_some() {
+                break;
+            }
+        }
+        self.stop.store(false, Ordering::Relaxed);
+        result
+    }
+
+    /// Negamax alpha-beta search of `depth` plies, writing the principal variation in `pv`
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        color: Color,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Movement>,
+    ) -> i32 {
+        if ply > 0 && self.is_draw(board) {
+            return 0;
+        }
+        if depth == 0 || ply >= MAX_DEPTH as usize {
+            return self.quiescence(board, color, ply, alpha, beta);
+        }
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        let mut movements = board.legal_moves(color);
+        if movements.is_empty() {
+            return terminal_score(board, color, ply);
+        }
+        self.order(board, &mut movements, ply);
+
+        for movement in movements {
+            let mut line = Vec::new();
+            let score = -self.play(board, color, &movement, |engine, board| {
+                engine.negamax(board, !color, depth - 1, ply + 1, -beta, -alpha, &mut line)
+            });
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                if !is_capture(board, &movement) {
+                    let killers = &mut self.killers[ply];
+                    if killers[0] != Some(movement) {
+                        killers[1] = killers[0];
+                        killers[0] = Some(movement);
+                    }
+                }
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(movement);
+                pv.extend(line);
+            }
+        }
+        alpha
+    }
+
+    /// Search only captures and promotions until the position is quiet, so the evaluation is not
+    /// taken in the middle of an exchange
+    fn quiescence(
+        &mut self,
+        board: &mut Board,
+        color: Color,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        let movements = board.legal_moves(color);
+        if movements.is_empty() {
+            return terminal_score(board, color, ply);
+        }
+        let stand_pat = evaluate(board, color);
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut tactical: Vec<Movement> = movements
+            .into_iter()
+            .filter(|movement| movement.promotion.is_some() || is_capture(board, movement))
+            .collect();
+        tactical.sort_by_key(|movement| Reverse(mvv_lva(board, movement)));
+
+        for movement in tactical {
+            let score = -self.play(board, color, &movement, |engine, board| {
+                engine.quiescence(board, !color, ply + 1, -beta, -alpha)
+            });
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// Play a legal `Movement`, run `f` on the resulting position and revert it
+    fn play(
+        &mut self,
+        board: &mut Board,
+        color: Color,
+        movement: &Movement,
+        f: impl FnOnce(&mut Self, &mut Board) -> i32,
+    ) -> i32 {
+        board
+            .with_movement(movement, |board| {
+                self.history.push(zobrist::position_hash(board, !color));
+                let score = f(self, board);
+                self.history.pop();
+                score
+            })
+            .expect("Movement is legal")
+    }
+
+    /// Sort the movements: previous principal variation first, then captures by MVV-LVA, then
+    /// killer movements
+    fn order(&self, board: &Board, movements: &mut [Movement], ply: usize) {
+        let pv_move = self.pv.get(ply).copied();
+        let killers = self.killers[ply];
+        movements.sort_by_key(|movement| {
+            Reverse(if Some(*movement) == pv_move {
+                i32::MAX
+            } else if is_capture(board, movement) || movement.promotion.is_some() {
+                1_000_000 + mvv_lva(board, movement)
+            } else if killers.contains(&Some(*movement)) {
+                500_000
+            } else {
+                0
+            })
+        });
+    }
+
+    /// Returns `true` if the position repeats one of the `Game` or the current line, or there is
+    /// not enough material to checkmate
+    fn is_draw(&self, board: &Board) -> bool {
+        let (current, previous) = self.history.split_last().expect("Root position is stored");
+        previous.contains(current) || board.has_insufficient_material()
+    }
+
+    /// Returns `true` once the search has to stop. The first iteration always completes, so
+    /// there is a movement to return
+    fn should_stop(&mut self) -> bool {
+        if self.root_depth > 1 && !self.stopped {
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.stopped
+    }
+}
+
+impl Game {
+    /// Search the best `Movement` of the `Color` to move
+    pub fn best_move(&self, limits: SearchLimits) -> SearchResult {
+        Engine::default().search(self, limits)
+    }
+}
+
+/// Returns the static evaluation of a `Board`, in centipawns from the point of view of `color`:
+/// material plus piece-square tables, the latter only for 8x8 boards
+pub fn evaluate(board: &Board, color: Color) -> i32 {
+    let standard = board.dimension == (Position { x: 7, y: 7 });
+    let non_pawn_material: i32 = board
+        .positions
+        .values()
+        .filter(|piece| !matches!(piece.kind, PieceKind::Pawn | PieceKind::King))
+        .map(|piece| piece_value(piece.kind))
+        .sum();
+    let endgame = non_pawn_material <= ENDGAME_MATERIAL;
+
+    board
+        .positions
+        .iter()
+        .map(|(position, piece)| {
+            let mut value = piece_value(piece.kind);
+            if standard {
+                value += square_value(*position, *piece, endgame);
+            }
+            if piece.color == color {
+                value
+            } else {
+                -value
+            }
+        })
+        .sum()
+}
+
+/// Returns the material value of a `PieceKind`, in centipawns. `Custom` pieces add up the
+/// value of their movement patterns
+fn piece_value(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::King => 0,
+        PieceKind::Queen => 900,
+        PieceKind::Rook => 500,
+        PieceKind::Bishop => 330,
+        PieceKind::Knight => 320,
+        PieceKind::Pawn => 100,
+        PieceKind::Archbishop => 825,
+        PieceKind::Chancellor => 875,
+        PieceKind::Custom { moves, .. } => [
+            (moves.king, 300),
+            (moves.rook, 500),
+            (moves.bishop, 330),
+            (moves.knight, 320),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, value)| value)
+        .sum(),
+    }
+}
+
+/// Returns the piece-square table bonus of a `ChessPiece` in a `Position` of an 8x8 board
+fn square_value(position: Position, piece: ChessPiece, endgame: bool) -> i32 {
+    let table = match piece.kind {
+        PieceKind::King if endgame => &KING_ENDGAME_TABLE,
+        PieceKind::King => &KING_TABLE,
+        PieceKind::Queen => &QUEEN_TABLE,
+        PieceKind::Rook => &ROOK_TABLE,
+        PieceKind::Bishop => &BISHOP_TABLE,
+        PieceKind::Knight
+        | PieceKind::Archbishop
+        | PieceKind::Chancellor
+        | PieceKind::Custom { .. } => &KNIGHT_TABLE,
+        PieceKind::Pawn => &PAWN_TABLE,
+    };
+    // Tables start at the 8th rank, so `Black` reads them mirrored
+    let row = if piece.color == White {
+        7 - position.y
+    } else {
+        position.y
+    };
+    table[(8 * row + position.x) as usize]
+}
+
+/// Returns the score of a position without legal movements: checkmate or stalemate
+fn terminal_score(board: &Board, color: Color, ply: usize) -> i32 {
+    if board.is_in_check(color) {
+        -MATE_SCORE + ply as i32
+    } else {
+        0
+    }
+}
+
+/// Returns `true` if a `Movement` captures a piece, including en passant
+fn is_capture(board: &Board, movement: &Movement) -> bool {
+    captured_kind(board, movement).is_some()
+}
+
+/// Returns the `PieceKind` captured by a `Movement`, if any
+fn captured_kind(board: &Board, movement: &Movement) -> Option<PieceKind> {
+    let mover = board.positions.get(&movement.from)?;
+    // A Chess960 castle takes the square of the own `Rook`
+    if let Some(piece) = board
+        .positions
+        .get(&movement.to)
+        .filter(|piece| piece.color != mover.color)
+    {
+        return Some(piece.kind);
+    }
+    let is_pawn = mover.kind == PieceKind::Pawn;
+    (is_pawn && board.en_passant == Some(movement.to) && movement.from.x != movement.to.x)
+        .then_some(PieceKind::Pawn)
+}
+
+/// Most Valuable Victim - Least Valuable Attacker score of a capture, plus the promotion gain
+fn mvv_lva(board: &Board, movement: &Movement) -> i32 {
+    let victim = captured_kind(board, movement).map_or(0, piece_value);
+    let attacker = board
+        .positions
+        .get(&movement.from)
+        .map_or(0, |piece| piece_value(piece.kind));
+    let promotion = movement
+        .promotion
+        .map_or(0, |promotion| piece_value(promotion.kind()));
+    10 * victim - attacker / 10 + promotion
+}
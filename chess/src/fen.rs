@@ -0,0 +1,401 @@
+//! FEN module.
+//!
+//! Import and export a `Game` position using the Forsyth–Edwards Notation
+//!
+use super::{
+    board::{Board, CastlingFiles, CastlingRights, Position, MAX_FILES, MAX_RANKS},
+    ensure, fail,
+    game::Game,
+    pieces::{
+        ChessPiece,
+        Color::{self, Black, White},
+        Piece,
+        PieceKind::{King, Pawn, Rook},
+    },
+};
+use std::{collections::HashMap, error::Error, fmt};
+
+/// FEN of the standard chess initial position
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// `Board` dimension and pieces described by a FEN
+type Placement = (Position, Vec<(Position, Box<dyn Piece>)>);
+
+/// List of Errors parsing a FEN
+#[derive(Debug, PartialEq, Eq)]
+pub enum FenError {
+    /// FEN must have 6 space separated fields (the last two can be omitted)
+    WrongFieldsNumber(usize),
+    /// Pieces placement field is not valid
+    InvalidPlacement(String),
+    /// Side to move is neither `w` nor `b`
+    InvalidSideToMove(String),
+    /// Castling field is neither `-` nor a combination of `KQkq` or the `Rook` files
+    InvalidCastling(String),
+    /// En passant field is neither `-` nor a square skipped by the last `Pawn` double step
+    InvalidEnPassant(String),
+    /// Halfmove clock is not a number
+    InvalidHalfmoveClock(String),
+    /// Fullmove number is not a positive number
+    InvalidFullmoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::WrongFieldsNumber(fields) => {
+                write!(f, "FEN must have 4 to 6 fields, found {}", fields)
+            }
+            FenError::InvalidPlacement(field) => write!(f, "{:?} is not a valid placement", field),
+            FenError::InvalidSideToMove(field) => {
+                write!(f, "{:?} is not a valid side to move", field)
+            }
+            FenError::InvalidCastling(field) => {
+                write!(f, "{:?} is not a valid castling availability", field)
+            }
+            FenError::InvalidEnPassant(field) => {
+                write!(f, "{:?} is not a valid en passant square", field)
+            }
+            FenError::InvalidHalfmoveClock(field) => {
+                write!(f, "{:?} is not a valid halfmove clock", field)
+            }
+            FenError::InvalidFullmoveNumber(field) => {
+                write!(f, "{:?} is not a valid fullmove number", field)
+            }
+        }
+    }
+}
+
+impl Error for FenError {}
+
+impl Game {
+    /// Creates a `Game` in the position described by a FEN
+    ///
+    /// Board dimension is taken from the number of ranks and files. Halfmove clock and
+    /// fullmove number are optional and default to `0` and `1`
+    ///
+    /// Chess960 positions are read from X-FEN (`KQkq` stand for the outermost `Rook`s, a file for
+    /// any other) and Shredder-FEN (the files of the castling `Rook`s, e.g. `HAha`)
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        ensure!(
+            (4..=6).contains(&fields.len()),
+            FenError::WrongFieldsNumber(fields.len())
+        );
+
+        let (dimension, pieces) = parse_placement(fields[0])?;
+        let color = match fields[1] {
+            "w" => White,
+            "b" => Black,
+            side => fail!(FenError::InvalidSideToMove(side.to_string())),
+        };
+        let en_passant = match fields[3] {
+            "-" => None,
+            square => Some(
+                square
+                    .parse::<Position>()
+                    .ok()
+                    .filter(|position| position.x <= dimension.x && position.y <= dimension.y)
+                    .ok_or(FenError::InvalidEnPassant(square.to_string()))?,
+            ),
+        };
+        let halfmove_clock = match fields.get(4) {
+            Some(clock) => clock
+                .parse::<u32>()
+                .map_err(|_| FenError::InvalidHalfmoveClock(clock.to_string()))?,
+            None => 0,
+        };
+        // Halfmoves played before the current one
+        let turn = match fields.get(5) {
+            Some(number) => number
+                .parse::<u32>()
+                .ok()
+                .and_then(|number| number.checked_sub(1))
+                .and_then(|moves| moves.checked_mul(2))
+                .and_then(|turn| turn.checked_add(u32::from(color == Black)))
+                .ok_or(FenError::InvalidFullmoveNumber(number.to_string()))?,
+            None => u32::from(color == Black),
+        };
+
+        let mut board = Board::new(dimension, Some(pieces));
+        let (castling_rights, castling_files) = parse_castling(fields[2], &board)?;
+        board.castling_rights = castling_rights;
+        if let Some(files) = castling_files {
+            board.castling_files = files;
+            board.chess960 = !files.is_standard(dimension);
+        }
+        if let Some(square) = en_passant {
+            ensure!(
+                is_en_passant_target(&board, square, color),
+                FenError::InvalidEnPassant(fields[3].to_string())
+            );
+        }
+        board.en_passant = en_passant;
+
+        let mut game = Game::new(board);
+        game.turn = turn;
+        game.halfmove_clock = halfmove_clock;
+        game.initial_fen = Some(game.to_fen());
+        Ok(game)
+    }
+
+    /// Returns the FEN describing the current position, in X-FEN for Chess960
+    pub fn to_fen(&self) -> String {
+        self.fen(false)
+    }
+
+    /// Returns the Shredder-FEN describing the current position, where castling availability is
+    /// given by the files of the castling `Rook`s, e.g. `HAha`
+    pub fn to_shredder_fen(&self) -> String {
+        self.fen(true)
+    }
+
+    /// Returns the FEN, with the castling `Rook` files if `shredder`
+    fn fen(&self, shredder: bool) -> String {
+        let color = Game::static_playing_color(self.turn);
+        let en_passant = self
+            .board
+            .en_passant
+            .map_or("-".to_string(), |position| position.to_string());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement(&self.board),
+            if color == White { "w" } else { "b" },
+            castling(&self.board, shredder),
+            en_passant,
+            self.halfmove_clock,
+            self.turn / 2 + 1,
+        )
+    }
+}
+
+/// Parse the pieces placement field, returning the `Board` dimension and its pieces
+fn parse_placement(field: &str) -> Result<Placement, FenError> {
+    let invalid = || FenError::InvalidPlacement(field.to_string());
+    let ranks: Vec<&str> = field.split('/').collect();
+    ensure!(ranks.len() <= MAX_RANKS as usize, invalid());
+    let max_y = ranks.len() as i32 - 1;
+    let mut max_x = None;
+    let mut pieces = Vec::new();
+
+    for (i, rank) in ranks.iter().enumerate() {
+        let y = max_y - i as i32;
+        let mut x: i32 = 0;
+        let mut empty_squares = String::new();
+
+        for symbol in rank.chars().chain(std::iter::once('/')) {
+            if symbol.is_ascii_digit() {
+                empty_squares.push(symbol);
+                continue;
+            }
+            if !empty_squares.is_empty() {
+                x = empty_squares
+                    .parse::<i32>()
+                    .ok()
+                    .and_then(|empty| x.checked_add(empty))
+                    .ok_or_else(invalid)?;
+                empty_squares.clear();
+            }
+            if symbol == '/' {
+                break;
+            }
+            let piece = ChessPiece::from_symbol(symbol).ok_or_else(invalid)?;
+            pieces.push((Position { x, y }, piece.to_piece()));
+            x = x.checked_add(1).ok_or_else(invalid)?;
+        }
+
+        // Every rank must have the same number of files, which must have a name
+        ensure!(
+            x > 0 && x <= MAX_FILES && *max_x.get_or_insert(x - 1) == x - 1,
+            invalid()
+        );
+    }
+
+    let dimension = Position {
+        x: max_x.ok_or_else(invalid)?,
+        y: max_y,
+    };
+    Ok((dimension, pieces))
+}
+
+/// Checks an en passant square was skipped by an enemy `Pawn` double step in the last movement,
+/// i.e. the `Pawn` stands in front of it and both the square and the one behind it are empty
+fn is_en_passant_target(board: &Board, square: Position, color: Color) -> bool {
+    let (rank, forward) = match color {
+        White => (board.dimension.y - 2, -1),
+        Black => (2, 1),
+    };
+    let pawn = Position {
+        x: square.x,
+        y: square.y + forward,
+    };
+    let origin = Position {
+        x: square.x,
+        y: square.y - forward,
+    };
+
+    square.y == rank
+        && board.positions.get(&pawn) == Some(&ChessPiece::new(Pawn, !color))
+        && !board.positions.contains_key(&square)
+        && !board.positions.contains_key(&origin)
+}
+
+/// Parse the castling availability field against the `Board`, returning the castling rights
+/// and, unless it is `-`, the castling files
+fn parse_castling(
+    field: &str,
+    board: &Board,
+) -> Result<(HashMap<Color, CastlingRights>, Option<CastlingFiles>), FenError> {
+    let invalid = || FenError::InvalidCastling(field.to_string());
+    let mut rights = [White, Black]
+        .into_iter()
+        .map(|color| {
+            (
+                color,
+                CastlingRights {
+                    king_side: false,
+                    queen_side: false,
+                },
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    if field == "-" {
+        return Ok((rights, None));
+    }
+    // Files of a `Board` without castling, used for the pieces out of their initial squares
+    let standard = board.castling_files;
+    let mut king = None;
+    let mut king_side_rook = None;
+    let mut queen_side_rook = None;
+    for symbol in field.chars() {
+        let color = if symbol.is_ascii_uppercase() {
+            White
+        } else {
+            Black
+        };
+        let row = if color == White { 0 } else { board.dimension.y };
+        let home_row = |piece: ChessPiece| {
+            (0..=board.dimension.x)
+                .filter(move |x| board.positions.get(&Position { x: *x, y: row }) == Some(&piece))
+        };
+        let rooks: Vec<i32> = home_row(ChessPiece::new(Rook, color)).collect();
+        let king_x = home_row(ChessPiece::new(King, color)).next();
+
+        // `K` and `Q` stand for the outermost `Rook` of each side
+        let (king_side, rook_x) = match symbol.to_ascii_lowercase() {
+            'k' => (
+                true,
+                king_x
+                    .and_then(|king_x| rooks.last().filter(|x| **x > king_x))
+                    .map_or(standard.king_side_rook, |x| *x),
+            ),
+            'q' => (
+                false,
+                king_x
+                    .and_then(|king_x| rooks.first().filter(|x| **x < king_x))
+                    .map_or(standard.queen_side_rook, |x| *x),
+            ),
+            file @ 'a'..='z' => {
+                let x = file as i32 - 'a' as i32;
+                let king_x = king_x.ok_or_else(invalid)?;
+                ensure!(x <= board.dimension.x && x != king_x, invalid());
+                (x > king_x, x)
+            }
+            _ => fail!(invalid()),
+        };
+
+        let color_rights = rights.get_mut(&color).expect("Color exists");
+        let (side, rook) = if king_side {
+            (&mut color_rights.king_side, &mut king_side_rook)
+        } else {
+            (&mut color_rights.queen_side, &mut queen_side_rook)
+        };
+        ensure!(!*side, invalid());
+        *side = true;
+        // Both colors castle from the same files
+        let king_x = king_x.unwrap_or(standard.king);
+        ensure!(*king.get_or_insert(king_x) == king_x, invalid());
+        ensure!(*rook.get_or_insert(rook_x) == rook_x, invalid());
+    }
+
+    let files = CastlingFiles {
+        king: king.unwrap_or(standard.king),
+        king_side_rook: king_side_rook.unwrap_or(standard.king_side_rook),
+        queen_side_rook: queen_side_rook.unwrap_or(standard.queen_side_rook),
+    };
+    Ok((rights, Some(files)))
+}
+
+/// Returns the pieces placement field
+fn placement(board: &Board) -> String {
+    (0..=board.dimension.y)
+        .rev()
+        .map(|y| {
+            let mut rank = String::new();
+            let mut empty_squares = 0;
+            for x in 0..=board.dimension.x {
+                match board.positions.get(&Position { x, y }) {
+                    Some(piece) => {
+                        if empty_squares > 0 {
+                            rank.push_str(&empty_squares.to_string());
+                            empty_squares = 0;
+                        }
+                        rank.push(piece.symbol());
+                    }
+                    None => empty_squares += 1,
+                }
+            }
+            if empty_squares > 0 {
+                rank.push_str(&empty_squares.to_string());
+            }
+            rank
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// Returns the castling availability field, only with the sides whose `King` and `Rook` are
+/// still in their initial squares
+///
+/// Sides are named `K` and `Q` unless `shredder`, or another `Rook` stands between the castling
+/// `Rook` and the edge of the `Board`, in which case the `Rook` file is used
+fn castling(board: &Board, shredder: bool) -> String {
+    let field: String = [White, Black]
+        .into_iter()
+        .flat_map(|color| {
+            let rights = board.castling_rights(color);
+            [(true, rights.king_side), (false, rights.queen_side)]
+                .into_iter()
+                .filter(move |(king_side, right)| {
+                    *right && board.can_castle_side(color, *king_side)
+                })
+                .map(move |(king_side, _)| {
+                    let rook = board.castling_rook_square(color, king_side);
+                    let outermost = !board.positions.iter().any(|(position, piece)| {
+                        *piece == ChessPiece::new(Rook, color)
+                            && position.y == rook.y
+                            && (position.x > rook.x) == king_side
+                            && position.x != rook.x
+                    });
+                    let symbol = match (shredder || !outermost, king_side) {
+                        (true, _) => (b'A' + rook.x as u8) as char,
+                        (false, true) => 'K',
+                        (false, false) => 'Q',
+                    };
+                    if color == White {
+                        symbol
+                    } else {
+                        symbol.to_ascii_lowercase()
+                    }
+                })
+        })
+        .collect();
+
+    if field.is_empty() {
+        "-".to_string()
+    } else {
+        field
+    }
+}
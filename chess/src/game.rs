@@ -3,15 +3,30 @@
 //! It provides the methods to create and interact with a Chess game
 //!
 use super::{
-    board::{Board, Movement, MovementError, Position},
-    ensure,
+    board::{Board, Movement, MovementError, MovementRecord, Position},
+    ensure, fail,
+    layout::{Layout, LayoutError, CHESS960_POSITIONS},
     pieces::{
-        Bishop,
         Color::{self, Black, White},
-        King, Knight, Pawn, Piece, PieceFactory, Queen, Rook,
+        PieceKind, Promotion,
     },
+    san::check_suffix,
+    zobrist::splitmix64,
 };
 use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Halfmoves without captures or `Pawn` movements to claim a draw (fifty-move rule)
+const FIFTY_MOVES: u32 = 100;
+/// Halfmoves without captures or `Pawn` movements to automatically draw (seventy-five-move rule)
+const SEVENTY_FIVE_MOVES: u32 = 150;
+/// Repetitions of a position to claim a draw
+const THREEFOLD: usize = 3;
+/// Repetitions of a position to automatically draw
+const FIVEFOLD: usize = 5;
 
 /// Player representation
 pub struct Player<'a> {
@@ -28,12 +43,108 @@ pub enum GameState {
     Ended,
 }
 
+/// Final result of a `Game`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+/// Reason why a `Game` ended
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum EndReason {
+    /// The side to move is in check and has no legal movements
+    Checkmate,
+    /// The side to move is not in check and has no legal movements
+    Stalemate,
+    /// No checkmate is possible with the remaining pieces
+    InsufficientMaterial,
+    /// Draw claimed after fifty moves without captures or `Pawn` movements
+    FiftyMoveRule,
+    /// Seventy-five moves without captures or `Pawn` movements
+    SeventyFiveMoveRule,
+    /// Draw claimed after the same position occurred three times
+    ThreefoldRepetition,
+    /// The same position occurred five times
+    FivefoldRepetition,
+    /// Result known by an endgame tablebase
+    Tablebase,
+    /// Result recorded in a PGN without a reason on the board, e.g. a resignation or a draw
+    /// by agreement
+    Recorded,
+}
+
+/// How an ended `Game` finished
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Outcome {
+    pub result: GameResult,
+    pub reason: EndReason,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = match self.result {
+            GameResult::WhiteWins => "White wins",
+            GameResult::BlackWins => "Black wins",
+            GameResult::Draw => "Draw",
+        };
+        write!(f, "{}. {}", self.reason, result)
+    }
+}
+
+impl fmt::Display for EndReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EndReason::Checkmate => write!(f, "Checkmate"),
+            EndReason::Stalemate => write!(f, "Stalemate"),
+            EndReason::InsufficientMaterial => write!(f, "Insufficient material"),
+            EndReason::FiftyMoveRule => write!(f, "Fifty-move rule"),
+            EndReason::SeventyFiveMoveRule => write!(f, "Seventy-five-move rule"),
+            EndReason::ThreefoldRepetition => write!(f, "Threefold repetition"),
+            EndReason::FivefoldRepetition => write!(f, "Fivefold repetition"),
+            EndReason::Tablebase => write!(f, "Tablebase adjudication"),
+            EndReason::Recorded => write!(f, "Recorded result"),
+        }
+    }
+}
+
+/// Movement played in a `Game`, with everything needed to undo it
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct PlayedMovement {
+    pub movement: Movement,
+    /// SAN of the movement, e.g. `Nf3`
+    pub san: String,
+    record: MovementRecord,
+    state: GameState,
+    outcome: Option<Outcome>,
+    halfmove_clock: u32,
+}
+
 /// Main game struct
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
 pub struct Game {
     pub board: Board,
     pub state: GameState,
     pub turn: u32,
+    /// How the `Game` finished, once `GameState::Ended`
+    #[serde(default)]
+    pub outcome: Option<Outcome>,
+    /// Halfmoves since the last capture or `Pawn` movement
+    #[serde(default)]
+    pub halfmove_clock: u32,
+    /// Hash of every position reached, used to detect repetitions
+    #[serde(default)]
+    pub position_history: Vec<u64>,
+    /// Movements played, the last one on top
+    #[serde(default)]
+    history: Vec<PlayedMovement>,
+    /// Movements undone, the next one to redo on top
+    #[serde(default)]
+    redo_stack: Vec<Movement>,
+    /// FEN of the initial position, if the `Game` was created from one
+    #[serde(default)]
+    pub initial_fen: Option<String>,
 }
 
 impl Game {
@@ -43,125 +154,86 @@ impl Game {
             board,
             state: GameState::Ready,
             turn: 0,
+            outcome: None,
+            halfmove_clock: 0,
+            position_history: Vec::new(),
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            initial_fen: None,
         }
     }
 
     /// Translate the movement syntax to an actual `Movement`
+    ///
+    /// The syntax is `a1a2` (from `a1` to `a2`), optionally followed by the piece a `Pawn`
+    /// is promoted to (`q`, `r`, `b` or `n`), e.g. `e7e8q`. Ranks may take several digits on
+    /// boards taller than 9 squares, e.g. `a10a11`
     fn translate_movement(&self, movement: String) -> Result<Movement, MovementError> {
-        ensure!(movement.len() == 4, MovementError::WrongCommand(movement));
-
-        let from = &movement[0..2];
-        let to = &movement[2..4];
-
-        let from_x_char = &from.split_at(1).0.chars().next().expect("exists");
-        let from_y_char = &from.split_at(1).1.chars().next().expect("exists");
-        let to_x_char = &to.split_at(1).0.chars().next().expect("exists");
-        let to_y_char = &to.split_at(1).1.chars().next().expect("exists");
+        let wrong_command = || MovementError::WrongCommand(movement.clone());
+        ensure!(movement.is_ascii(), wrong_command());
 
-        let from_x = match from_x_char {
-            'a'..='z' => Some(*from_x_char as i32 - 'a' as i32),
-            'A'..='Z' => Some(*from_x_char as i32 - 'A' as i32),
-            _ => None, // Return None if the character is not in the range a-z or A-Z
-        }
-        .ok_or(MovementError::WrongCommand(movement.clone()))?;
-
-        let to_x = match to_x_char {
-            'a'..='z' => Some(*to_x_char as i32 - 'a' as i32),
-            'A'..='Z' => Some(*to_x_char as i32 - 'A' as i32),
-            _ => None, // Return None if the character is not in the range a-z or A-Z
-        }
-        .ok_or(MovementError::WrongCommand(movement.clone()))?;
+        let letters: Vec<usize> = movement
+            .char_indices()
+            .filter(|(_, c)| c.is_ascii_alphabetic())
+            .map(|(index, _)| index)
+            .collect();
+        let (to_index, promotion_index) = match letters[..] {
+            [0, to] => (to, None),
+            [0, to, last] if last == movement.len() - 1 => (to, Some(last)),
+            _ => fail!(wrong_command()),
+        };
+        let squares = &movement[..promotion_index.unwrap_or(movement.len())];
+        let from = squares[..to_index]
+            .parse::<Position>()
+            .map_err(|_| wrong_command())?;
+        let to = squares[to_index..]
+            .parse::<Position>()
+            .map_err(|_| wrong_command())?;
 
-        let from_y = from_y_char
-            .to_digit(10)
-            .ok_or(MovementError::WrongCommand(movement.clone()))? as i32;
-        let to_y = to_y_char
-            .to_digit(10)
-            .ok_or(MovementError::WrongCommand(movement))? as i32;
+        let promotion = match promotion_index {
+            Some(index) => Some(
+                movement[index..]
+                    .chars()
+                    .next()
+                    .and_then(Promotion::from_symbol)
+                    .ok_or_else(wrong_command)?,
+            ),
+            None => None,
+        };
 
         Ok(Movement {
-            from: Position {
-                x: from_x,
-                y: from_y - 1,
-            },
-            to: Position {
-                x: to_x,
-                y: to_y - 1,
-            },
+            from,
+            to,
+            promotion,
         })
     }
 
     /// Set a board with the standard chess pieces layout
     pub fn set_board(&mut self) {
-        let mut pieces = self.build_pieces(White);
-        pieces.extend(self.build_pieces(Black));
-        self.board.add_pieces(pieces)
+        self.set_layout(&Layout::standard())
     }
 
-    /// Returns the standard chess pieces and their positions
-    fn build_pieces(&mut self, color: Color) -> Vec<(Position, Box<dyn Piece>)> {
-        let (row, offset) = match color {
-            White => (0, 1),
-            Black => (self.board.dimension.y, -1),
-        };
-        //King
-        let king = (
-            Position { x: 4, y: row },
-            PieceFactory::create::<King>(color),
-        );
-        //King
-        let queen = (
-            Position { x: 3, y: row },
-            PieceFactory::create::<Queen>(color),
-        );
-        // Rooks
-        let rook_left = (
-            Position { x: 0, y: row },
-            PieceFactory::create::<Rook>(color),
-        );
-        let rook_right = (
-            Position { x: 7, y: row },
-            PieceFactory::create::<Rook>(color),
-        );
-        // Knights
-        let knight_left = (
-            Position { x: 1, y: row },
-            PieceFactory::create::<Knight>(color),
-        );
-        let knight_right = (
-            Position { x: 6, y: row },
-            PieceFactory::create::<Knight>(color),
-        );
-        // Bishops
-        let bishop_left = (
-            Position { x: 2, y: row },
-            PieceFactory::create::<Bishop>(color),
-        );
-        let bishop_right = (
-            Position { x: 5, y: row },
-            PieceFactory::create::<Bishop>(color),
-        );
-
-        let mut pieces = vec![
-            king,
-            queen,
-            rook_left,
-            rook_right,
-            knight_left,
-            knight_right,
-            bishop_left,
-            bishop_right,
-        ];
-
-        // Paws
-        for x in 0..=self.board.dimension.x {
-            let pawn = (
-                Position { x, y: row + offset },
-                PieceFactory::create::<Pawn>(color),
-            );
-            pieces.push(pawn)
-        }
-        pieces
+    /// Set a board with a Chess960 layout, given by its Scharnagl number (from `0` to `959`,
+    /// `518` is the standard one) or chosen randomly. Returns the Scharnagl number
+    ///
+    /// The `King` always stands between the `Rook`s and the bishops on squares of different
+    /// colors, and castling is encoded as the `King` capturing its own `Rook`
+    ///
+    /// Can return `Err` if the Scharnagl number is not a Chess960 layout, leaving the `Board`
+    /// untouched
+    pub fn set_board_960(&mut self, index: Option<u32>) -> Result<u32, LayoutError> {
+        let index = index.unwrap_or_else(|| {
+            let seed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_nanos() as u64);
+            splitmix64(seed) as u32 % CHESS960_POSITIONS
+        });
+        let layout = Layout::chess960(index)?;
+        self.set_layout(&layout);
+        // Even the standard layout castles as in Chess960
+        self.board.chess960 = true;
+        self.initial_fen = Some(self.to_fen());
+        Ok(index)
     }
 
     /// Change `GameState` to `OnGoing`
@@ -184,19 +256,205 @@ impl Game {
         self.turn += 1;
     }
 
-    /// Move a chess piece with a String command
+    /// Move a chess piece with a String command, either in coordinates (`e2e4`) or SAN (`e4`)
+    ///
+    /// The `Game` ends when the next `Color` to move is checkmated or stalemated
     pub fn move_piece(&mut self, movement_string: String) -> Result<(), MovementError> {
-        let movement = self.translate_movement(movement_string.trim().to_string())?;
-        let res = self.board.move_piece(self.playing_color(), &movement);
-        if res.is_ok() {
-            self.new_turn()
+        ensure!(self.state != GameState::Ended, MovementError::GameEnded);
+        let command = movement_string.trim();
+        let legal_moves = self.legal_moves();
+        let movement = if is_coordinates(command) {
+            self.translate_movement(command.to_string())?
+        } else {
+            self.board.resolve_san(&legal_moves, command)?
+        };
+        self.play(movement, &legal_moves)?;
+        self.redo_stack.clear();
+        Ok(())
+    }
+
+    /// Play a `Movement`, recording it in the history. `legal_moves` are those of the `Color`
+    /// to move, used to disambiguate its SAN
+    fn play(&mut self, movement: Movement, legal_moves: &[Movement]) -> Result<(), MovementError> {
+        let color = self.playing_color();
+        self.board.check_movement(color, &movement)?;
+        if self.position_history.is_empty() {
+            self.position_history.push(self.hash());
+        }
+
+        let is_pawn = self
+            .board
+            .positions
+            .get(&movement.from)
+            .is_some_and(|piece| piece.kind == PieceKind::Pawn);
+        let dead_pieces = self.dead_pieces_count();
+        let san = self.board.san_without_suffix(&movement, legal_moves);
+        let record = self.board.play_movement(color, &movement)?;
+        let mut played = PlayedMovement {
+            movement,
+            san,
+            record,
+            state: self.state,
+            outcome: self.outcome,
+            halfmove_clock: self.halfmove_clock,
+        };
+
+        if is_pawn || self.dead_pieces_count() > dead_pieces {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        self.new_turn();
+        self.position_history.push(self.hash());
+
+        let opponent = self.playing_color();
+        let has_legal_moves = self.board.has_legal_moves(opponent);
+        played.san.extend(check_suffix(
+            self.board.is_in_check(opponent),
+            has_legal_moves,
+        ));
+        self.history.push(played);
+        self.update_outcome(has_legal_moves);
+        Ok(())
+    }
+
+    /// Take back the last `Movement`, restoring the `Game` as it was before it. Returns the
+    /// `Movement`, or `None` if there is nothing to undo
+    pub fn undo(&mut self) -> Option<Movement> {
+        let played = self.history.pop()?;
+        self.board
+            .revert_movement(played.record)
+            .expect("It is the last movement played");
+        self.turn -= 1;
+        self.state = played.state;
+        self.outcome = played.outcome;
+        self.halfmove_clock = played.halfmove_clock;
+        self.position_history.pop();
+        self.redo_stack.push(played.movement);
+        Some(played.movement)
+    }
+
+    /// Play again the last undone `Movement`. Returns the `Movement`, or `None` if there is
+    /// nothing to redo
+    ///
+    /// Playing any other `Movement` discards the undone ones
+    pub fn redo(&mut self) -> Option<Movement> {
+        if self.state == GameState::Ended {
+            return None;
         }
-        res
+        let movement = self.redo_stack.pop()?;
+        let legal_moves = self.legal_moves();
+        self.play(movement, &legal_moves)
+            .expect("Movement was legal in this position");
+        Some(movement)
+    }
+
+    /// Returns the movements played, from first to last
+    pub fn history(&self) -> &[PlayedMovement] {
+        &self.history
+    }
+
+    /// Returns the number of captured pieces of both colors
+    fn dead_pieces_count(&self) -> usize {
+        self.board.dead_pieces(White).len() + self.board.dead_pieces(Black).len()
+    }
+
+    /// Returns how many times the current position has occurred
+    fn repetitions(&self) -> usize {
+        let current = self.hash();
+        self.position_history
+            .iter()
+            .filter(|hash| **hash == current)
+            .count()
+    }
+
+    /// Returns the draw the `Color` to move could claim, either by the fifty-move rule or by
+    /// threefold repetition
+    pub fn claimable_draw(&self) -> Option<EndReason> {
+        if self.repetitions() >= THREEFOLD {
+            Some(EndReason::ThreefoldRepetition)
+        } else if self.halfmove_clock >= FIFTY_MOVES {
+            Some(EndReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// End the `Game` as a draw if it can be claimed. Returns the `Outcome` if so
+    pub fn claim_draw(&mut self) -> Option<Outcome> {
+        if self.state == GameState::Ended {
+            return None;
+        }
+        let reason = self.claimable_draw()?;
+        self.end_with(Outcome {
+            result: GameResult::Draw,
+            reason,
+        });
+        self.outcome
+    }
+
+    /// End the `Game` if the `Color` to move has no legal movements or it is an automatic draw
+    fn update_outcome(&mut self, has_legal_moves: bool) {
+        let color = self.playing_color();
+        if has_legal_moves {
+            let reason = if self.board.has_insufficient_material() {
+                EndReason::InsufficientMaterial
+            } else if self.repetitions() >= FIVEFOLD {
+                EndReason::FivefoldRepetition
+            } else if self.halfmove_clock >= SEVENTY_FIVE_MOVES {
+                EndReason::SeventyFiveMoveRule
+            } else {
+                return;
+            };
+            self.end_with(Outcome {
+                result: GameResult::Draw,
+                reason,
+            });
+            return;
+        }
+
+        let outcome = if self.board.is_in_check(color) {
+            Outcome {
+                result: match color {
+                    White => GameResult::BlackWins,
+                    Black => GameResult::WhiteWins,
+                },
+                reason: EndReason::Checkmate,
+            }
+        } else {
+            Outcome {
+                result: GameResult::Draw,
+                reason: EndReason::Stalemate,
+            }
+        };
+        self.end_with(outcome);
+    }
+
+    /// Change `GameState` to `Ended` recording its `Outcome`
+    pub(crate) fn end_with(&mut self, outcome: Outcome) {
+        self.outcome = Some(outcome);
+        self.end();
+    }
+
+    /// Returns every legal `Movement` of the `Color` to move
+    pub fn legal_moves(&mut self) -> Vec<Movement> {
+        let color = self.playing_color();
+        self.board.legal_moves(color)
+    }
+
+    /// Returns the SAN of the last movement played
+    pub fn last_move(&self) -> Option<&str> {
+        self.history.last().map(|played| played.san.as_str())
+    }
+
+    /// Returns `true` if the `Color` to move is in check
+    pub fn is_in_check(&self) -> bool {
+        self.board.is_in_check(self.playing_color())
     }
 
     /// Returns current game's turn `Color`
     fn playing_color(&self) -> Color {
-        if self.turn % 2 == 0 {
+        if self.turn.is_multiple_of(2) {
             White
         } else {
             Black
@@ -205,72 +463,68 @@ impl Game {
 
     /// Return playing color for a certain turn
     pub fn static_playing_color(turn: u32) -> Color {
-        if turn % 2 == 0 {
+        if turn.is_multiple_of(2) {
             White
         } else {
             Black
         }
     }
 
-    /// Returns a printable board
+    /// Returns a printable board, from the point of view of `color`
     pub fn print_board(&self, color: Color) -> String {
         use colored::*;
         let mut result: Vec<String> = Vec::new();
         let x_max = self.board.dimension.x;
         let y_max = self.board.dimension.y;
-        let mut board: Vec<Vec<char>> = Vec::new();
-        let mut left_numbers: Vec<usize> = (0..=y_max as usize).collect();
-        let mut bottom_letters: Vec<usize> = (0..=x_max as usize).collect();
-
-        for y in 0..=y_max {
-            let mut row = Vec::new();
-            for x in 0..=x_max {
-                let position = Position {
-                    x: x as i32,
-                    y: y as i32,
-                };
-                let piece = self
+        // Rank numbers are right-aligned when some take several digits
+        let width = (y_max + 1).to_string().len();
+        let (rows, files): (Vec<i32>, Vec<i32>) = match color {
+            White => ((0..=y_max).rev().collect(), (0..=x_max).collect()),
+            Black => ((0..=y_max).collect(), (0..=x_max).rev().collect()),
+        };
+
+        for y in &rows {
+            // Print left numbers
+            result.push(format!("{:>width$} ", y + 1));
+            for x in &files {
+                let square = self
                     .board
                     .positions
-                    .get(&position)
+                    .get(&Position { x: *x, y: *y })
                     .map_or(' ', |p| p.symbol());
-                row.push(piece);
-            }
-            board.push(row);
-        }
-
-        if color == White {
-            board.reverse();
-        }
-        if color == Black {
-            left_numbers.reverse();
-            bottom_letters.reverse();
-            for y in 0..=y_max {
-                board[y as usize].reverse();
-            }
-        }
-
-        for y in 0..=y_max {
-            // Print left numbers
-            result.push(format!("{} ", left_numbers[(y_max - y) as usize] + 1));
-            for x in 0..=x_max {
-                let square = board[y as usize][x as usize];
-                if (x + y) % 2 == 0 {
+                if (x + y) % 2 == 1 {
                     result.push(format!("{}", format!(" {} ", square).white().on_black()));
                 } else {
                     result.push(format!("{}", format!(" {} ", square).black().on_white()));
                 }
             }
-            result.push(format!("\n"));
+            result.push("\n".to_string());
         }
 
         // Print bottom letters
-        result.push(format!("  "));
-        for x in bottom_letters {
+        result.push(" ".repeat(width + 1));
+        for x in files {
             result.push(format!(" {} ", (b'a' + x as u8) as char));
         }
-        result.push(format!("\n"));
+        result.push("\n".to_string());
 
         result.iter().flat_map(|s| s.chars()).collect()
     }
 }
+
+/// Returns `true` if a command is in coordinates, e.g. `e2e4`, `a10a11` or `e7e8q`. SAN never
+/// starts with a lowercase file followed by a rank, as pawns only name their origin file
+fn is_coordinates(command: &str) -> bool {
+    strip_square(command)
+        .and_then(strip_square)
+        .is_some_and(|promotion| {
+            promotion.len() <= 1 && promotion.chars().all(|c| c.is_ascii_alphabetic())
+        })
+}
+
+/// Strips a square from the start of a command, e.g. `e2` from `e2e4`
+fn strip_square(command: &str) -> Option<&str> {
+    let rank = command.strip_prefix(|c: char| c.is_ascii_lowercase())?;
+    let rest = rank.trim_start_matches(|c: char| c.is_ascii_digit());
+    (rest.len() < rank.len()).then_some(rest)
+}
@@ -0,0 +1,291 @@
+//! Layout module.
+//!
+//! Initial arrangement of the pieces for boards of any dimension
+//!
+use super::{
+    board::{Board, CastlingFiles, CastlingRights, PawnRules, Position, MAX_FILES, MAX_RANKS},
+    ensure,
+    game::Game,
+    pieces::{
+        ChessPiece,
+        Color::{Black, White},
+        Piece, PieceKind,
+    },
+};
+use std::{error::Error, fmt};
+
+/// Number of Chess960 starting positions
+pub const CHESS960_POSITIONS: u32 = 960;
+/// Squares taken by the two `Knight`s among the five left after placing bishops and `Queen`,
+/// by Scharnagl number
+const CHESS960_KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+/// Back ranks and `Pawn` rows of both colors
+const MIN_RANKS: i32 = 4;
+
+/// List of Errors describing a `Layout`
+#[derive(Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// The symbol is not a piece, or it is a `Pawn`
+    InvalidPiece(char),
+    /// The back rank must have exactly one `King`
+    KingsNumber(usize),
+    /// There must be room for the back ranks and the `Pawn` rows
+    TooFewRanks(i32),
+    /// Files are named by a single letter, so there can be at most `MAX_FILES`
+    TooManyFiles(usize),
+    /// Ranks are named by up to two digits, so there can be at most `MAX_RANKS`
+    TooManyRanks(i32),
+    /// Chess960 layouts are numbered from `0` to `CHESS960_POSITIONS - 1`
+    InvalidChess960(u32),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::InvalidPiece(symbol) => {
+                write!(f, "{:?} is not a valid back rank piece", symbol)
+            }
+            LayoutError::KingsNumber(kings) => {
+                write!(f, "The back rank must have one King, found {}", kings)
+            }
+            LayoutError::TooFewRanks(ranks) => {
+                write!(
+                    f,
+                    "{} ranks are too few, at least {} are needed",
+                    ranks, MIN_RANKS
+                )
+            }
+            LayoutError::TooManyFiles(files) => {
+                write!(
+                    f,
+                    "{} files are too many, at most {} are allowed",
+                    files, MAX_FILES
+                )
+            }
+            LayoutError::TooManyRanks(ranks) => {
+                write!(
+                    f,
+                    "{} ranks are too many, at most {} are allowed",
+                    ranks, MAX_RANKS
+                )
+            }
+            LayoutError::InvalidChess960(index) => {
+                write!(
+                    f,
+                    "{} is not a Chess960 layout, they are numbered from 0 to {}",
+                    index,
+                    CHESS960_POSITIONS - 1
+                )
+            }
+        }
+    }
+}
+
+impl Error for LayoutError {}
+
+/// Initial arrangement of the pieces: a back rank, mirrored for `Black`, with a row of pawns
+/// in front of each
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Layout {
+    /// Pieces of the first rank, from the `a` file
+    back_rank: Vec<PieceKind>,
+    ranks: i32,
+    /// Whether the `King` can castle with the outermost `Rook` of each side
+    castling: bool,
+    /// `Pawn` rules of the `Board`
+    pawn_rules: PawnRules,
+}
+
+impl Layout {
+    /// Creates a `Layout` from the symbols of its back rank, e.g. `RNBQKBNR`, with as many files
+    /// as symbols (up to `MAX_FILES`) and a certain number of ranks (up to `MAX_RANKS`)
+    pub fn new(
+        back_rank: &str,
+        ranks: i32,
+        castling: bool,
+        pawn_rules: PawnRules,
+    ) -> Result<Self, LayoutError> {
+        let back_rank = back_rank
+            .chars()
+            .map(|symbol| {
+                ChessPiece::from_symbol(symbol.to_ascii_uppercase())
+                    .map(|piece| piece.kind)
+                    .filter(|kind| *kind != PieceKind::Pawn)
+                    .ok_or(LayoutError::InvalidPiece(symbol))
+            })
+            .collect::<Result<Vec<PieceKind>, LayoutError>>()?;
+        let kings = back_rank
+            .iter()
+            .filter(|kind| **kind == PieceKind::King)
+            .count();
+        ensure!(kings == 1, LayoutError::KingsNumber(kings));
+        ensure!(
+            back_rank.len() <= MAX_FILES as usize,
+            LayoutError::TooManyFiles(back_rank.len())
+        );
+        ensure!(ranks >= MIN_RANKS, LayoutError::TooFewRanks(ranks));
+        ensure!(ranks <= MAX_RANKS, LayoutError::TooManyRanks(ranks));
+
+        Ok(Layout {
+            back_rank,
+            ranks,
+            castling,
+            pawn_rules,
+        })
+    }
+
+    /// Standard chess 8x8 layout
+    pub fn standard() -> Self {
+        Self::new("RNBQKBNR", 8, true, PawnRules::default()).expect("Valid layout")
+    }
+
+    /// Capablanca chess 10x8 layout, with an `Archbishop` and a `Chancellor`. The `King` castles
+    /// three squares sideways, to the `i` or `c` file
+    pub fn capablanca() -> Self {
+        Self::new("RNABQKBCNR", 8, true, PawnRules::default()).expect("Valid layout")
+    }
+
+    /// Los Alamos chess 6x6 layout, without bishops nor castling. Pawns neither move two
+    /// squares nor promote to a `Bishop`
+    pub fn los_alamos() -> Self {
+        let pawn_rules = PawnRules {
+            double_step: false,
+            bishop_promotion: false,
+        };
+        Self::new("RNQKNR", 6, false, pawn_rules).expect("Valid layout")
+    }
+
+    /// Gardner minichess 5x5 layout, without castling nor `Pawn` double steps
+    pub fn gardner() -> Self {
+        let pawn_rules = PawnRules {
+            double_step: false,
+            ..PawnRules::default()
+        };
+        Self::new("RNBQK", 5, false, pawn_rules).expect("Valid layout")
+    }
+
+    /// Chess960 layout by Scharnagl number, from `0` to `959`: the light and dark squared
+    /// bishops, then the `Queen` and the `Knight`s in the empty squares, and finally `Rook`,
+    /// `King` and `Rook` in the remaining ones
+    pub fn chess960(index: u32) -> Result<Self, LayoutError> {
+        ensure!(
+            index < CHESS960_POSITIONS,
+            LayoutError::InvalidChess960(index)
+        );
+        let index = index as usize;
+        let mut rank = [None; 8];
+        rank[2 * (index % 4) + 1] = Some(PieceKind::Bishop);
+        rank[2 * (index / 4 % 4)] = Some(PieceKind::Bishop);
+
+        let mut place = |kind: PieceKind, nth: usize| {
+            let x = (0..8)
+                .filter(|x| rank[*x].is_none())
+                .nth(nth)
+                .expect("Square is empty");
+            rank[x] = Some(kind);
+        };
+        let (first_knight, second_knight) = CHESS960_KNIGHTS[index / 96];
+        place(PieceKind::Queen, index / 16 % 6);
+        // The first `Knight` takes an empty square, so the second one is one fewer away
+        place(PieceKind::Knight, first_knight);
+        place(PieceKind::Knight, second_knight - 1);
+        place(PieceKind::Rook, 0);
+        place(PieceKind::King, 0);
+        place(PieceKind::Rook, 0);
+
+        Ok(Layout {
+            back_rank: rank
+                .iter()
+                .map(|kind| kind.expect("Every square is taken"))
+                .collect(),
+            ranks: 8,
+            castling: true,
+            pawn_rules: PawnRules::default(),
+        })
+    }
+
+    /// Returns the `Board` dimension
+    pub fn dimension(&self) -> Position {
+        Position {
+            x: self.back_rank.len() as i32 - 1,
+            y: self.ranks - 1,
+        }
+    }
+
+    /// Returns the pieces of both colors and their positions
+    pub fn pieces(&self) -> Vec<(Position, Box<dyn Piece>)> {
+        let dimension = self.dimension();
+        let mut pieces = Vec::new();
+        for (color, row, offset) in [(White, 0, 1), (Black, dimension.y, -1)] {
+            for (x, kind) in self.back_rank.iter().enumerate() {
+                let position = Position {
+                    x: x as i32,
+                    y: row,
+                };
+                pieces.push((position, ChessPiece::new(*kind, color).to_piece()));
+            }
+            for x in 0..=dimension.x {
+                let position = Position { x, y: row + offset };
+                pieces.push((position, ChessPiece::new(PieceKind::Pawn, color).to_piece()));
+            }
+        }
+        pieces
+    }
+
+    /// Returns the initial files of the `King` and the outermost `Rook` of each side
+    pub(crate) fn castling_files(&self) -> CastlingFiles {
+        let files = |kind: PieceKind| {
+            self.back_rank
+                .iter()
+                .enumerate()
+                .filter(move |(_, piece)| **piece == kind)
+                .map(|(x, _)| x as i32)
+        };
+        let king = files(PieceKind::King).next().expect("King exists");
+        CastlingFiles {
+            king,
+            king_side_rook: files(PieceKind::Rook)
+                .filter(|x| *x > king)
+                .max()
+                .unwrap_or(self.dimension().x),
+            queen_side_rook: files(PieceKind::Rook).find(|x| *x < king).unwrap_or(0),
+        }
+    }
+}
+
+impl Game {
+    /// Set a `Board` with the dimension, pieces and `PawnRules` of a `Layout`, replacing the
+    /// current one, e.g. `Layout::standard()` for standard chess
+    ///
+    /// Castling is played moving the `King` to its destination when the `Rook`s are in the
+    /// corners and far enough from the `King` (see `CastlingFiles::is_standard`), and as in
+    /// Chess960 otherwise
+    pub fn set_layout(&mut self, layout: &Layout) {
+        let dimension = layout.dimension();
+        let mut board = Board::new(dimension, Some(layout.pieces()));
+        let files = layout.castling_files();
+        let rook = |x: i32| layout.back_rank.get(x as usize) == Some(&PieceKind::Rook);
+        let rights = CastlingRights {
+            king_side: layout.castling && rook(files.king_side_rook),
+            queen_side: layout.castling && rook(files.queen_side_rook),
+        };
+        for color in [White, Black] {
+            board.castling_rights.insert(color, rights);
+        }
+        board.castling_files = files;
+        board.chess960 = layout.castling && !files.is_standard(dimension);
+        board.pawn_rules = layout.pawn_rules;
+        self.board = board;
+    }
+}
@@ -2,7 +2,18 @@
 //!
 //! This library provides a way of setting and playing a Chess Game
 //!
+pub mod bitboard;
 pub mod board;
+pub mod book;
 mod decl_macros;
+pub mod engine;
+pub mod fen;
 pub mod game;
+pub mod layout;
+pub mod perft;
+pub mod pgn;
 pub mod pieces;
+pub mod san;
+#[cfg(feature = "syzygy")]
+pub mod tablebase;
+pub mod zobrist;
@@ -0,0 +1,63 @@
+//! Perft module.
+//!
+//! Count the leaf nodes of the legal movement tree, to check the movement generation against
+//! well-known reference values
+//!
+use super::{
+    board::{Board, Movement},
+    game::Game,
+    pieces::Color,
+};
+
+impl Board {
+    /// Returns the number of legal `Movement` sequences of `depth` plies, starting with `color`
+    pub fn perft(&mut self, color: Color, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let movements = self.legal_moves(color);
+        // Leaf nodes do not need to be played
+        if depth == 1 {
+            return movements.len() as u64;
+        }
+        movements
+            .iter()
+            .map(|movement| {
+                self.with_movement(movement, |board| board.perft(!color, depth - 1))
+                    .expect("Movement is legal")
+            })
+            .sum()
+    }
+
+    /// Returns the `perft` of `depth - 1` plies after every legal `Movement` of `color`
+    pub fn divide(&mut self, color: Color, depth: u32) -> Vec<(Movement, u64)> {
+        let movements = self.legal_moves(color);
+        movements
+            .into_iter()
+            .map(|movement| {
+                let nodes = self
+                    .with_movement(&movement, |board| {
+                        board.perft(!color, depth.saturating_sub(1))
+                    })
+                    .expect("Movement is legal");
+                (movement, nodes)
+            })
+            .collect()
+    }
+}
+
+impl Game {
+    /// Returns the number of legal `Movement` sequences of `depth` plies from the current
+    /// position. Draw and repetition rules are not taken into account
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        let color = Game::static_playing_color(self.turn);
+        self.board.perft(color, depth)
+    }
+
+    /// Returns the `perft` of `depth - 1` plies after every legal `Movement`, to find which one
+    /// differs from a reference
+    pub fn divide(&mut self, depth: u32) -> Vec<(Movement, u64)> {
+        let color = Game::static_playing_color(self.turn);
+        self.board.divide(color, depth)
+    }
+}
@@ -0,0 +1,419 @@
+//! PGN module.
+//!
+//! Import and export a `Game` in Portable Game Notation
+//!
+use super::{
+    board::MovementError,
+    ensure, fail,
+    fen::{FenError, STARTING_FEN},
+    game::{EndReason, Game, GameResult, Outcome},
+    pieces::Color::White,
+};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt, iter::Peekable, str::Chars};
+
+/// Maximum length of a movetext line
+const LINE_LENGTH: usize = 79;
+
+/// Game termination markers
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// List of Errors parsing a PGN. Games are numbered from `1`, as well as plies within a game
+#[derive(Debug, PartialEq, Eq)]
+pub enum PgnError {
+    /// The PGN does not contain any game
+    NoGame,
+    /// A tag pair is not `[Name "value"]`
+    InvalidTag { game: usize, tag: String },
+    /// The `FEN` tag is not valid
+    InvalidFen { game: usize, error: FenError },
+    /// A movement can not be parsed or played
+    InvalidMovement {
+        game: usize,
+        ply: usize,
+        movement: String,
+        error: MovementError,
+    },
+    /// A comment `{` is never closed
+    UnclosedComment { game: usize },
+    /// Variation parentheses `(` `)` do not match
+    UnbalancedVariation { game: usize },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::NoGame => write!(f, "There is not any game"),
+            PgnError::InvalidTag { game, tag } => {
+                write!(f, "Game {}: {:?} is not a valid tag", game, tag)
+            }
+            PgnError::InvalidFen { game, error } => write!(f, "Game {}: {}", game, error),
+            PgnError::InvalidMovement {
+                game,
+                ply,
+                movement,
+                error,
+            } => write!(f, "Game {}, ply {}: {:?}. {}", game, ply, movement, error),
+            PgnError::UnclosedComment { game } => write!(f, "Game {}: unclosed comment", game),
+            PgnError::UnbalancedVariation { game } => {
+                write!(f, "Game {}: unbalanced variation", game)
+            }
+        }
+    }
+}
+
+impl Error for PgnError {}
+
+/// Game read from a PGN
+#[derive(Debug)]
+pub struct PgnGame {
+    /// Tag pairs, in the order they appear
+    pub tags: Vec<(String, String)>,
+    /// `Game` after replaying the mainline
+    pub game: Game,
+    /// Game termination marker: `1-0`, `0-1`, `1/2-1/2` or `*`
+    pub result: String,
+}
+
+impl PgnGame {
+    /// Returns the value of a tag
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Tags and mainline movements of a game, before replaying them
+#[derive(Default)]
+struct RawGame {
+    tags: Vec<(String, String)>,
+    movements: Vec<String>,
+}
+
+impl RawGame {
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.movements.is_empty()
+    }
+
+    /// Replay the movements from the standard initial position, or the `FEN` tag one unless
+    /// `SetUp` is `0`, castling as in Chess960 for that `Variant`. The result ends the `Game`
+    /// if its movements did not, e.g. after a resignation
+    fn replay(self, index: usize, result: &str) -> Result<PgnGame, PgnError> {
+        let tag = |name: &str| {
+            self.tags
+                .iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let fen = match (tag("SetUp"), tag("FEN")) {
+            (Some("0"), _) | (_, None) => STARTING_FEN,
+            (_, Some(fen)) => fen,
+        };
+        let mut game =
+            Game::from_fen(fen).map_err(|error| PgnError::InvalidFen { game: index, error })?;
+        if tag("Variant").is_some_and(|variant| variant.eq_ignore_ascii_case("Chess960")) {
+            game.board.chess960 = true;
+        }
+        game.start();
+
+        for (i, movement) in self.movements.into_iter().enumerate() {
+            if let Err(error) = game.move_piece(movement.clone()) {
+                fail!(PgnError::InvalidMovement {
+                    game: index,
+                    ply: i + 1,
+                    movement,
+                    error,
+                });
+            }
+        }
+
+        if let (true, Some(result)) = (game.is_ongoing(), parse_result(result)) {
+            game.end_with(Outcome {
+                result,
+                reason: EndReason::Recorded,
+            });
+        }
+
+        Ok(PgnGame {
+            tags: self.tags,
+            game,
+            result: result.to_string(),
+        })
+    }
+}
+
+/// Parse every game of a PGN, replaying their mainlines
+///
+/// Comments, NAGs, move numbers and variations are skipped
+pub fn parse_pgn(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = Vec::new();
+    let mut current = RawGame::default();
+    let mut chars = pgn.chars().peekable();
+    let mut line_start = true;
+    let mut depth = 0;
+
+    while let Some(symbol) = chars.next() {
+        let index = games.len() + 1;
+        match symbol {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            // Escape mechanism: the whole line is ignored
+            '%' if line_start => skip_line(&mut chars),
+            ';' => skip_line(&mut chars),
+            '{' => {
+                ensure!(
+                    chars.any(|symbol| symbol == '}'),
+                    PgnError::UnclosedComment { game: index }
+                );
+            }
+            '(' => depth += 1,
+            ')' => {
+                ensure!(depth > 0, PgnError::UnbalancedVariation { game: index });
+                depth -= 1;
+            }
+            '[' if depth == 0 => {
+                // A game without termination marker ends when the next one starts
+                let index = if current.movements.is_empty() {
+                    index
+                } else {
+                    games.push(std::mem::take(&mut current).replay(index, "*")?);
+                    index + 1
+                };
+                let tag = read_tag(&mut chars);
+                let tag_pair = parse_tag(&tag).ok_or(PgnError::InvalidTag { game: index, tag })?;
+                current.tags.push(tag_pair);
+            }
+            symbol if symbol.is_whitespace() => {}
+            symbol => {
+                let mut token = symbol.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || "{}()[];".contains(next) {
+                        break;
+                    }
+                    token.push(next);
+                    chars.next();
+                }
+                if depth > 0 || token.starts_with('$') {
+                    // Variations and NAGs are skipped
+                } else if RESULTS.contains(&token.as_str()) {
+                    games.push(std::mem::take(&mut current).replay(index, &token)?);
+                } else if let Some(movement) = strip_move_number(&token) {
+                    current.movements.push(movement.to_string());
+                }
+            }
+        }
+        line_start = false;
+    }
+
+    let index = games.len() + 1;
+    ensure!(depth == 0, PgnError::UnbalancedVariation { game: index });
+    if !current.is_empty() {
+        games.push(current.replay(index, "*")?);
+    }
+    Ok(games)
+}
+
+impl Game {
+    /// Creates a `Game` replaying the mainline of the first game of a PGN
+    pub fn from_pgn(pgn: &str) -> Result<Self, PgnError> {
+        parse_pgn(pgn)?
+            .into_iter()
+            .next()
+            .map(|pgn_game| pgn_game.game)
+            .ok_or(PgnError::NoGame)
+    }
+}
+
+/// Returns the `GameResult` of a game termination marker, `None` for `*`
+fn parse_result(token: &str) -> Option<GameResult> {
+    match token {
+        "1-0" => Some(GameResult::WhiteWins),
+        "0-1" => Some(GameResult::BlackWins),
+        "1/2-1/2" => Some(GameResult::Draw),
+        _ => None,
+    }
+}
+
+/// Skip the remaining characters of the line
+fn skip_line(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|symbol| *symbol != '\n').is_some() {}
+}
+
+/// Returns the content of a tag pair, up to the closing `]` not within quotes
+fn read_tag(chars: &mut Peekable<Chars>) -> String {
+    let mut tag = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for symbol in chars.by_ref() {
+        match symbol {
+            ']' if !quoted => break,
+            '"' if !escaped => quoted = !quoted,
+            _ => {}
+        }
+        escaped = symbol == '\\' && !escaped;
+        tag.push(symbol);
+    }
+    tag
+}
+
+/// Parse the content of a tag pair, e.g. `Event "Casual game"`
+fn parse_tag(tag: &str) -> Option<(String, String)> {
+    let (name, value) = tag.trim().split_once(char::is_whitespace)?;
+    if !name
+        .chars()
+        .all(|symbol| symbol.is_ascii_alphanumeric() || symbol == '_')
+    {
+        return None;
+    }
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(symbol) = chars.next() {
+        match symbol {
+            '\\' => unescaped.push(chars.next()?),
+            '"' => return None,
+            _ => unescaped.push(symbol),
+        }
+    }
+    Some((name.to_string(), unescaped))
+}
+
+/// Returns the movement of a token without its move number, e.g. `e4` for `1.e4`, or `None` if it
+/// is only a move number, e.g. `1.` or `1...`
+fn strip_move_number(token: &str) -> Option<&str> {
+    let without_number = token.trim_start_matches(|symbol: char| symbol.is_ascii_digit());
+    let movement = if without_number.starts_with('.') {
+        without_number.trim_start_matches('.')
+    } else {
+        token
+    };
+    (!movement.is_empty()).then_some(movement)
+}
+
+/// Seven Tag Roster of a PGN, but the `Result` which is taken from the `Game`
+///
+/// Unknown values are `?`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct PgnTags {
+    pub event: String,
+    pub site: String,
+    /// `YYYY.MM.DD`, with `?` for the unknown digits
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for PgnTags {
+    fn default() -> Self {
+        PgnTags {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: "????.??.??".to_string(),
+            round: "?".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+        }
+    }
+}
+
+impl Game {
+    /// Returns the PGN result token: `1-0`, `0-1`, `1/2-1/2`, or `*` if the `Game` has not
+    /// finished
+    pub fn pgn_result(&self) -> &'static str {
+        match self.outcome.map(|outcome| outcome.result) {
+            Some(GameResult::WhiteWins) => "1-0",
+            Some(GameResult::BlackWins) => "0-1",
+            Some(GameResult::Draw) => "1/2-1/2",
+            None => "*",
+        }
+    }
+
+    /// Returns the `Game` in PGN: Seven Tag Roster, `SetUp` and `FEN` tags if it did not
+    /// start from the standard position, `Variant` for Chess960, and the SAN movetext
+    pub fn to_pgn(&self, tags: &PgnTags) -> String {
+        let result = self.pgn_result();
+        let mut headers = vec![
+            ("Event", tags.event.as_str()),
+            ("Site", tags.site.as_str()),
+            ("Date", tags.date.as_str()),
+            ("Round", tags.round.as_str()),
+            ("White", tags.white.as_str()),
+            ("Black", tags.black.as_str()),
+            ("Result", result),
+        ];
+        let fen = self.start_fen();
+        if fen != STARTING_FEN {
+            headers.push(("SetUp", "1"));
+            headers.push(("FEN", &fen));
+        }
+        if self.board.chess960 {
+            headers.push(("Variant", "Chess960"));
+        }
+
+        let mut pgn: String = headers
+            .into_iter()
+            .map(|(name, value)| format!("[{} \"{}\"]\n", name, escape(value)))
+            .collect();
+        pgn.push('\n');
+        pgn.push_str(&wrap(self.movetext_tokens(result)));
+        pgn.push('\n');
+        pgn
+    }
+
+    /// Returns the FEN of the initial position. Unless the `Game` was created from one, it is
+    /// derived by undoing every movement played
+    fn start_fen(&self) -> String {
+        if let Some(fen) = &self.initial_fen {
+            return fen.clone();
+        }
+        let mut game = self.clone();
+        while game.undo().is_some() {}
+        game.to_fen()
+    }
+
+    /// Returns the movetext split in tokens: move numbers, SAN movements and the result
+    fn movetext_tokens(&self, result: &str) -> Vec<String> {
+        let history = self.history();
+        let first_turn = self.turn.saturating_sub(history.len() as u32);
+        let mut tokens = Vec::new();
+
+        for (i, played) in history.iter().enumerate() {
+            let turn = first_turn + i as u32;
+            let number = turn / 2 + 1;
+            if Game::static_playing_color(turn) == White {
+                tokens.push(format!("{}.", number));
+            } else if i == 0 {
+                tokens.push(format!("{}...", number));
+            }
+            tokens.push(played.san.clone());
+        }
+        tokens.push(result.to_string());
+        tokens
+    }
+}
+
+/// Join tokens with spaces in lines no longer than `LINE_LENGTH`
+fn wrap(tokens: Vec<String>) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for token in tokens {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + token.len() <= LINE_LENGTH => {
+                line.push(' ');
+                line.push_str(&token);
+            }
+            _ => lines.push(token),
+        }
+    }
+    lines.join("\n")
+}
+
+/// Escape quotes and backslashes of a tag value
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -2,7 +2,7 @@
 //!
 //! Chess pieces declarations and implementations
 //!
-use chess_proc_macros::Piece;
+pub use chess_proc_macros::Piece;
 use serde::{Deserialize, Serialize};
 use std::{any::Any, fmt::Debug, ops::Not};
 
@@ -23,6 +23,152 @@ impl Not for Color {
     }
 }
 
+/// Kind of a chess piece, which determines how it moves
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum PieceKind {
+    King,
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+    Pawn,
+    /// Moves as a `Bishop` or a `Knight`, as in Capablanca chess
+    Archbishop,
+    /// Moves as a `Rook` or a `Knight`, as in Capablanca chess
+    Chancellor,
+    /// Piece declared out of this crate with `#[derive(Piece)]`, named by an uppercase `symbol`
+    /// and moving as any combination of the `King`, `Rook`, `Bishop` and `Knight`. FEN and SAN
+    /// only parse the built-in symbols, so they are played in coordinates
+    Custom {
+        symbol: char,
+        moves: Moves,
+    },
+}
+
+/// Movement patterns a piece combines, e.g. `bishop` and `knight` for the `Archbishop`
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Moves {
+    /// One square in any direction, without castling
+    pub king: bool,
+    /// Any number of squares along a rank or a file
+    pub rook: bool,
+    /// Any number of squares along a diagonal
+    pub bishop: bool,
+    /// Jump as a `Knight`
+    pub knight: bool,
+}
+
+impl PieceKind {
+    /// Returns the uppercase symbol, e.g. `N` for `Knight`
+    pub fn symbol(&self) -> char {
+        match self {
+            PieceKind::King => 'K',
+            PieceKind::Queen => 'Q',
+            PieceKind::Rook => 'R',
+            PieceKind::Bishop => 'B',
+            PieceKind::Knight => 'N',
+            PieceKind::Pawn => 'P',
+            PieceKind::Archbishop => 'A',
+            PieceKind::Chancellor => 'C',
+            PieceKind::Custom { symbol, .. } => *symbol,
+        }
+    }
+
+    /// Returns the movement patterns. `Pawn`s have none, as their movements depend on their
+    /// `Color` and on the `Board`
+    pub fn moves(&self) -> Moves {
+        let (king, rook, bishop, knight) = match self {
+            PieceKind::King => (true, false, false, false),
+            PieceKind::Queen => (false, true, true, false),
+            PieceKind::Rook => (false, true, false, false),
+            PieceKind::Bishop => (false, false, true, false),
+            PieceKind::Knight => (false, false, false, true),
+            PieceKind::Pawn => (false, false, false, false),
+            PieceKind::Archbishop => (false, false, true, true),
+            PieceKind::Chancellor => (false, true, false, true),
+            PieceKind::Custom { moves, .. } => return *moves,
+        };
+        Moves {
+            king,
+            rook,
+            bishop,
+            knight,
+        }
+    }
+}
+
+/// Compact representation of a chess piece, as stored in the `Board`
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct ChessPiece {
+    pub kind: PieceKind,
+    pub color: Color,
+}
+
+impl ChessPiece {
+    pub fn new(kind: PieceKind, color: Color) -> Self {
+        ChessPiece { kind, color }
+    }
+
+    /// Returns the piece for a FEN symbol: uppercase for `White`, lowercase for `Black`. Custom
+    /// pieces are not known by their symbol, so they are never returned
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        let kind = match symbol.to_ascii_uppercase() {
+            'K' => PieceKind::King,
+            'Q' => PieceKind::Queen,
+            'R' => PieceKind::Rook,
+            'B' => PieceKind::Bishop,
+            'N' => PieceKind::Knight,
+            'P' => PieceKind::Pawn,
+            'A' => PieceKind::Archbishop,
+            'C' => PieceKind::Chancellor,
+            _ => return None,
+        };
+        let color = if symbol.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some(ChessPiece::new(kind, color))
+    }
+
+    /// Returns the FEN symbol: uppercase for `White`, lowercase for `Black`
+    pub fn symbol(&self) -> char {
+        match self.color {
+            Color::White => self.kind.symbol(),
+            Color::Black => self.kind.symbol().to_ascii_lowercase(),
+        }
+    }
+
+    /// Builds the `Piece` of the same kind and `Color`
+    pub fn to_piece(&self) -> Box<dyn Piece> {
+        match self.kind {
+            PieceKind::King => PieceFactory::create::<King>(self.color),
+            PieceKind::Queen => PieceFactory::create::<Queen>(self.color),
+            PieceKind::Rook => PieceFactory::create::<Rook>(self.color),
+            PieceKind::Bishop => PieceFactory::create::<Bishop>(self.color),
+            PieceKind::Knight => PieceFactory::create::<Knight>(self.color),
+            PieceKind::Pawn => PieceFactory::create::<Pawn>(self.color),
+            PieceKind::Archbishop => PieceFactory::create::<Archbishop>(self.color),
+            PieceKind::Chancellor => PieceFactory::create::<Chancellor>(self.color),
+            PieceKind::Custom { .. } => Box::new(CustomPiece {
+                kind: self.kind,
+                color: self.color,
+            }),
+        }
+    }
+}
+
+impl From<&dyn Piece> for ChessPiece {
+    fn from(piece: &dyn Piece) -> Self {
+        ChessPiece::new(piece.kind(), piece.color())
+    }
+}
+
+/// Chess piece trait. Pieces move as the `PieceKind` they return
+///
+/// `#[derive(Piece)]` implements it for a tuple struct holding its `Color`, returning the
+/// `PieceKind` of the same name. Other types declare theirs with `#[piece(kind = Knight)]`, or
+/// their own `Custom` kind with `#[piece(symbol = 'W', moves(king, knight))]`
 pub trait Piece: Debug + AnyPiece + Send + Sync {
     fn new(color: Color) -> Self
     where
@@ -30,46 +176,27 @@ pub trait Piece: Debug + AnyPiece + Send + Sync {
     fn color(&self) -> Color {
         Color::White
     }
+    fn kind(&self) -> PieceKind;
     fn is_king(&self) -> bool {
-        self.as_any().downcast_ref::<King>().is_some()
+        self.kind() == PieceKind::King
     }
     fn is_queen(&self) -> bool {
-        self.as_any().downcast_ref::<Queen>().is_some()
+        self.kind() == PieceKind::Queen
     }
     fn is_rook(&self) -> bool {
-        self.as_any().downcast_ref::<Rook>().is_some()
+        self.kind() == PieceKind::Rook
     }
     fn is_bishop(&self) -> bool {
-        self.as_any().downcast_ref::<Bishop>().is_some()
+        self.kind() == PieceKind::Bishop
     }
     fn is_knight(&self) -> bool {
-        self.as_any().downcast_ref::<Knight>().is_some()
+        self.kind() == PieceKind::Knight
     }
     fn is_pawn(&self) -> bool {
-        self.as_any().downcast_ref::<Pawn>().is_some()
+        self.kind() == PieceKind::Pawn
     }
     fn symbol(&self) -> char {
-        let mut symbol = if self.is_king() {
-            'K'
-        } else if self.is_queen() {
-            'Q'
-        } else if self.is_rook() {
-            'R'
-        } else if self.is_bishop() {
-            'B'
-        } else if self.is_knight() {
-            'N'
-        } else if self.is_pawn() {
-            'P'
-        } else {
-            unreachable!()
-        };
-
-        if self.color() == Color::Black {
-            let symbol_string = symbol.to_lowercase().collect::<String>();
-            symbol = symbol_string.chars().next().unwrap();
-        }
-        symbol
+        ChessPiece::new(self.kind(), self.color()).symbol()
     }
 }
 
@@ -102,6 +229,31 @@ pub struct Knight(Color);
 #[derive(Debug, Piece)]
 pub struct Pawn(Color);
 
+#[derive(Debug, Piece)]
+pub struct Archbishop(Color);
+
+#[derive(Debug, Piece)]
+pub struct Chancellor(Color);
+
+/// Piece of a `Custom` kind, as rebuilt from the `Board`
+#[derive(Debug)]
+struct CustomPiece {
+    kind: PieceKind,
+    color: Color,
+}
+
+impl Piece for CustomPiece {
+    fn new(_color: Color) -> Self {
+        unreachable!("Custom pieces are only built from their ChessPiece")
+    }
+    fn color(&self) -> Color {
+        self.color
+    }
+    fn kind(&self) -> PieceKind {
+        self.kind
+    }
+}
+
 pub struct PieceFactory;
 
 impl PieceFactory {
@@ -109,3 +261,55 @@ impl PieceFactory {
         Box::new(T::new(color))
     }
 }
+
+/// Pieces a `Pawn` can be promoted to when reaching the last row
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Promotion {
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+}
+
+impl Promotion {
+    /// Returns the `Promotion` for a piece symbol (either case), e.g. `q` or `Q` for `Queen`
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol.to_ascii_lowercase() {
+            'q' => Some(Promotion::Queen),
+            'r' => Some(Promotion::Rook),
+            'b' => Some(Promotion::Bishop),
+            'n' => Some(Promotion::Knight),
+            _ => None,
+        }
+    }
+
+    /// Returns the lowercase symbol used in movement commands
+    pub fn symbol(&self) -> char {
+        match self {
+            Promotion::Queen => 'q',
+            Promotion::Rook => 'r',
+            Promotion::Bishop => 'b',
+            Promotion::Knight => 'n',
+        }
+    }
+
+    /// Returns the `PieceKind` of the promoted piece
+    pub fn kind(&self) -> PieceKind {
+        match self {
+            Promotion::Queen => PieceKind::Queen,
+            Promotion::Rook => PieceKind::Rook,
+            Promotion::Bishop => PieceKind::Bishop,
+            Promotion::Knight => PieceKind::Knight,
+        }
+    }
+
+    /// Builds the promoted `Piece` for a certain `Color`
+    pub fn create(&self, color: Color) -> Box<dyn Piece> {
+        match self {
+            Promotion::Queen => PieceFactory::create::<Queen>(color),
+            Promotion::Rook => PieceFactory::create::<Rook>(color),
+            Promotion::Bishop => PieceFactory::create::<Bishop>(color),
+            Promotion::Knight => PieceFactory::create::<Knight>(color),
+        }
+    }
+}
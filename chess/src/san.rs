@@ -0,0 +1,277 @@
+//! SAN module.
+//!
+//! Parse and render movements in Standard Algebraic Notation, e.g. `Nf3`, `exd5`, `O-O` or `e8=Q+`
+//!
+use super::{
+    board::{Board, Movement, MovementError, Position},
+    ensure, fail,
+    game::Game,
+    pieces::{Color, PieceKind, Promotion},
+};
+
+/// King side castle
+const CASTLE_KING_SIDE: &str = "O-O";
+/// Queen side castle
+const CASTLE_QUEEN_SIDE: &str = "O-O-O";
+
+/// Movement described by a SAN, before resolving it against a `Board`
+#[derive(Debug)]
+struct SanMovement {
+    /// Uppercase symbol of the moving `Piece`, `None` for a `Pawn`
+    piece: Option<char>,
+    /// Origin file, if disambiguated
+    file: Option<i32>,
+    /// Origin rank, if disambiguated
+    rank: Option<i32>,
+    to: Position,
+    promotion: Option<Promotion>,
+}
+
+impl Board {
+    /// Resolves a SAN to the only legal `Movement` of a certain `Color` it describes
+    ///
+    /// Capture markers and check, mate and annotation suffixes (`+`, `#`, `!`, `?`) are
+    /// accepted but not required
+    pub fn parse_san(&mut self, color: Color, san: &str) -> Result<Movement, MovementError> {
+        let legal_moves = self.legal_moves(color);
+        self.resolve_san(&legal_moves, san)
+    }
+
+    /// Resolves a SAN like `parse_san`, among the already generated legal movements
+    pub(crate) fn resolve_san(
+        &self,
+        legal_moves: &[Movement],
+        san: &str,
+    ) -> Result<Movement, MovementError> {
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+        let matches: Vec<&Movement> = match text {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let king_side = text.len() == CASTLE_KING_SIDE.len();
+                legal_moves
+                    .iter()
+                    .filter(|movement| {
+                        self.is_castle(movement) && (movement.to.x > movement.from.x) == king_side
+                    })
+                    .collect()
+            }
+            _ => {
+                let san_movement =
+                    parse_san_movement(text).ok_or(MovementError::WrongCommand(san.to_string()))?;
+                legal_moves
+                    .iter()
+                    .filter(|movement| self.matches(&san_movement, movement))
+                    .collect()
+            }
+        };
+
+        match matches.as_slice() {
+            [movement] => Ok(**movement),
+            [] => fail!(MovementError::IllegalMovement),
+            _ => fail!(MovementError::AmbiguousMovement(san.to_string())),
+        }
+    }
+
+    /// Returns the SAN of a legal `Movement` of a certain `Color`, including the check (`+`)
+    /// or checkmate (`#`) suffix
+    pub fn to_san(&mut self, color: Color, movement: &Movement) -> Result<String, MovementError> {
+        ensure!(
+            self.is_legal_move(color, movement),
+            MovementError::IllegalMovement
+        );
+
+        let legal_moves = self.legal_moves(color);
+        let mut san = self.san_without_suffix(movement, &legal_moves);
+        let opponent = !color;
+        let suffix = self.with_movement(movement, |board| {
+            check_suffix(board.is_in_check(opponent), board.has_legal_moves(opponent))
+        })?;
+        san.extend(suffix);
+        Ok(san)
+    }
+
+    /// Returns the SAN of a legal `Movement` without its check or checkmate suffix, given the
+    /// legal movements of its `Color`
+    pub(crate) fn san_without_suffix(
+        &self,
+        movement: &Movement,
+        legal_moves: &[Movement],
+    ) -> String {
+        if self.is_castle(movement) {
+            return if movement.to.x > movement.from.x {
+                CASTLE_KING_SIDE.to_string()
+            } else {
+                CASTLE_QUEEN_SIDE.to_string()
+            };
+        }
+
+        let piece = self
+            .positions
+            .get(&movement.from)
+            .expect("Movement is legal");
+        let is_pawn = piece.kind == PieceKind::Pawn;
+        let symbol = piece.kind.symbol();
+        let is_capture = self.positions.contains_key(&movement.to)
+            || (is_pawn && self.en_passant == Some(movement.to));
+
+        let mut san = String::new();
+        if is_pawn {
+            if is_capture {
+                san.push(file_symbol(movement.from.x));
+            }
+        } else {
+            san.push(symbol);
+            san.push_str(&self.disambiguation(movement, legal_moves));
+        }
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&movement.to.to_string());
+        if let Some(promotion) = movement.promotion {
+            san.push('=');
+            san.push(promotion.symbol().to_ascii_uppercase());
+        }
+        san
+    }
+
+    /// Returns `true` if a legal `Movement` is the one described by a `SanMovement`
+    fn matches(&self, san_movement: &SanMovement, movement: &Movement) -> bool {
+        let Some(piece) = self.positions.get(&movement.from) else {
+            return false;
+        };
+        let symbol = (piece.kind != PieceKind::Pawn).then(|| piece.kind.symbol());
+
+        !self.is_castle(movement)
+            && symbol == san_movement.piece
+            && movement.to == san_movement.to
+            && movement.promotion == san_movement.promotion
+            && san_movement.file.is_none_or(|x| x == movement.from.x)
+            && san_movement.rank.is_none_or(|y| y == movement.from.y)
+    }
+
+    /// Returns the origin file, rank or square needed to tell a `Movement` apart from the ones
+    /// of other pieces of the same kind reaching the same square
+    fn disambiguation(&self, movement: &Movement, legal_moves: &[Movement]) -> String {
+        let symbol = self
+            .positions
+            .get(&movement.from)
+            .map(|piece| piece.symbol());
+        let others: Vec<Position> = legal_moves
+            .iter()
+            .filter(|other| {
+                other.to == movement.to
+                    && other.from != movement.from
+                    && self.positions.get(&other.from).map(|piece| piece.symbol()) == symbol
+            })
+            .map(|other| other.from)
+            .collect();
+
+        let from = movement.from;
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|other| other.x != from.x) {
+            file_symbol(from.x).to_string()
+        } else if others.iter().all(|other| other.y != from.y) {
+            (from.y + 1).to_string()
+        } else {
+            from.to_string()
+        }
+    }
+}
+
+impl Game {
+    /// Resolves a SAN to the legal `Movement` of the `Color` to move it describes
+    pub fn parse_san(&mut self, san: &str) -> Result<Movement, MovementError> {
+        let color = Game::static_playing_color(self.turn);
+        self.board.parse_san(color, san)
+    }
+
+    /// Returns the SAN of a legal `Movement` of the `Color` to move
+    pub fn to_san(&mut self, movement: &Movement) -> Result<String, MovementError> {
+        let color = Game::static_playing_color(self.turn);
+        self.board.to_san(color, movement)
+    }
+}
+
+/// Parse a SAN which is not a castle, e.g. `Nbd7`, `exd5` or `e8=Q`
+fn parse_san_movement(text: &str) -> Option<SanMovement> {
+    if !text.is_ascii() {
+        return None;
+    }
+    let mut rest = text;
+
+    let piece = match rest.chars().next() {
+        Some(symbol @ ('K' | 'Q' | 'R' | 'B' | 'N' | 'A' | 'C')) => {
+            rest = &rest[1..];
+            Some(symbol)
+        }
+        _ => None,
+    };
+
+    // Promotion, either `e8=Q` or `e8Q`
+    let mut promotion = None;
+    if let Some((square, symbol)) = rest.split_once('=') {
+        let mut symbols = symbol.chars();
+        promotion = Some(Promotion::from_symbol(symbols.next()?)?);
+        if symbols.next().is_some() {
+            return None;
+        }
+        rest = square;
+    } else if let Some(symbol @ ('Q' | 'R' | 'B' | 'N')) = rest.chars().last() {
+        promotion = Promotion::from_symbol(symbol);
+        rest = &rest[..rest.len() - 1];
+    }
+
+    // Destination square: a lowercase file followed by the rank digits
+    let rank_start = rest.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let file_start = rank_start.checked_sub(1)?;
+    if rank_start == rest.len() || !rest[file_start..].starts_with(|c: char| c.is_ascii_lowercase())
+    {
+        return None;
+    }
+    let to = rest[file_start..].parse().ok()?;
+    rest = &rest[..file_start];
+    rest = rest.strip_suffix('x').unwrap_or(rest);
+
+    // Disambiguation: origin file, rank or both
+    let file = match rest.chars().next() {
+        Some(file @ 'a'..='z') => {
+            rest = &rest[1..];
+            Some(file as i32 - 'a' as i32)
+        }
+        _ => None,
+    };
+    let rank = if rest.is_empty() {
+        None
+    } else {
+        if !rest.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some(rest.parse::<i32>().ok()? - 1)
+    };
+
+    Some(SanMovement {
+        piece,
+        file,
+        rank,
+        to,
+        promotion,
+    })
+}
+
+/// Returns the suffix of a SAN given whether the opponent is in check and has legal movements
+/// left: `+` for check, `#` for checkmate
+pub(crate) fn check_suffix(in_check: bool, has_legal_moves: bool) -> Option<char> {
+    if !in_check {
+        None
+    } else if has_legal_moves {
+        Some('+')
+    } else {
+        Some('#')
+    }
+}
+
+/// Returns the letter of a file, e.g. `e` for `4`
+fn file_symbol(x: i32) -> char {
+    (b'a' + x as u8) as char
+}
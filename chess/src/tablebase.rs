@@ -0,0 +1,194 @@
+//! Tablebase module.
+//!
+//! Probe Syzygy endgame tablebases stored on local disk for the perfect result of a `Game`
+//!
+//! Only built with the `syzygy` feature, as its `shakmaty` dependencies are GPL-3.0-or-later
+//!
+use super::{
+    board::Position,
+    ensure,
+    game::{EndReason, Game, GameResult, GameState, Outcome},
+    pieces::Color::{Black, White},
+};
+use shakmaty::{fen::Fen, CastlingMode, Chess, Position as _};
+use shakmaty_syzygy::{AmbiguousWdl, Metric, SyzygyError};
+use std::{error::Error, fmt, path::Path};
+
+/// List of Errors probing a tablebase
+#[derive(Debug, PartialEq, Eq)]
+pub enum TablebaseError {
+    /// The directory can not be read
+    Io(String),
+    /// Tablebases only cover 8x8 boards
+    UnsupportedBoard,
+    /// The position has more pieces than the largest table
+    TooManyPieces(usize),
+    /// Tables do not contain positions with castling rights
+    Castling,
+    /// A table needed by the position is missing or corrupted
+    Probe(String),
+}
+
+impl fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TablebaseError::Io(error) => write!(f, "Tablebase can not be read: {}", error),
+            TablebaseError::UnsupportedBoard => write!(f, "Tablebases only cover 8x8 boards"),
+            TablebaseError::TooManyPieces(pieces) => {
+                write!(f, "{} pieces are too many for the tablebase", pieces)
+            }
+            TablebaseError::Castling => {
+                write!(f, "Tablebases do not cover positions with castling rights")
+            }
+            TablebaseError::Probe(error) => write!(f, "Tablebase probe failed: {}", error),
+        }
+    }
+}
+
+impl Error for TablebaseError {}
+
+/// Win/draw/loss of the `Color` to move, taking the fifty-move rule into account
+#[derive(PartialEq, Eq, Debug, Clone, Copy, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    /// Loss saved by the fifty-move rule
+    BlessedLoss,
+    Draw,
+    /// Win frustrated by the fifty-move rule
+    CursedWin,
+    Win,
+}
+
+/// Result of probing a position
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Probe {
+    pub wdl: Wdl,
+    /// Distance to zero: halfmoves to the next capture or `Pawn` movement with best play.
+    /// Positive when the `Color` to move wins, negative when it loses and `0` for draws.
+    /// `None` without the DTZ table of the position
+    pub dtz: Option<i32>,
+}
+
+/// Syzygy WDL (`.rtbw`) and, optionally, DTZ (`.rtbz`) tables of a local directory
+pub struct Tablebase {
+    tables: shakmaty_syzygy::Tablebase<Chess>,
+}
+
+impl fmt::Debug for Tablebase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tablebase")
+            .field("max_pieces", &self.max_pieces())
+            .finish()
+    }
+}
+
+impl Tablebase {
+    /// Add the tables of a directory. Files are opened lazily, when first probed
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, TablebaseError> {
+        let mut tables = shakmaty_syzygy::Tablebase::new();
+        tables
+            .add_directory(dir)
+            .map_err(|error| TablebaseError::Io(error.to_string()))?;
+        Ok(Tablebase { tables })
+    }
+
+    /// Returns the number of pieces, kings included, of the largest table
+    pub fn max_pieces(&self) -> usize {
+        self.tables.max_pieces()
+    }
+
+    /// Probe the current position of a `Game`. The WDL tables of the position, and of every
+    /// position reachable by captures and promotions, are needed
+    ///
+    /// With its DTZ tables, the fifty-move rule is applied from the current halfmove clock, and
+    /// when DTZ rounding leaves unclear whether it saves the game, the result is `CursedWin` or
+    /// `BlessedLoss`. Without them, the result is the one right after a capture or `Pawn` movement
+    pub fn probe(&self, game: &Game) -> Result<Probe, TablebaseError> {
+        ensure!(
+            game.board.dimension == (Position { x: 7, y: 7 }),
+            TablebaseError::UnsupportedBoard
+        );
+        let pieces = game.board.positions.len();
+        ensure!(
+            pieces <= self.max_pieces(),
+            TablebaseError::TooManyPieces(pieces)
+        );
+
+        let position: Chess = game
+            .to_fen()
+            .parse::<Fen>()
+            .map_err(|error| TablebaseError::Probe(error.to_string()))?
+            .into_position(CastlingMode::Standard)
+            .map_err(|error| TablebaseError::Probe(error.to_string()))?;
+        let wdl = self
+            .tables
+            .probe_wdl_after_zeroing(&position)
+            .map_err(probe_error)?;
+        let dtz = match self.tables.probe_dtz(&position) {
+            Ok(dtz) => dtz,
+            Err(SyzygyError::MissingTable {
+                metric: Metric::Dtz,
+                ..
+            }) => {
+                return Ok(Probe {
+                    wdl: wdl.into(),
+                    dtz: None,
+                })
+            }
+            Err(error) => return Err(probe_error(error)),
+        };
+        let wdl = match AmbiguousWdl::from_dtz_and_halfmoves(dtz, position.halfmoves()) {
+            AmbiguousWdl::Loss => Wdl::Loss,
+            AmbiguousWdl::MaybeLoss | AmbiguousWdl::BlessedLoss => Wdl::BlessedLoss,
+            AmbiguousWdl::Draw => Wdl::Draw,
+            AmbiguousWdl::MaybeWin | AmbiguousWdl::CursedWin => Wdl::CursedWin,
+            AmbiguousWdl::Win => Wdl::Win,
+        };
+        Ok(Probe {
+            wdl,
+            dtz: Some(dtz.ignore_rounding().0),
+        })
+    }
+}
+
+impl From<shakmaty_syzygy::Wdl> for Wdl {
+    fn from(wdl: shakmaty_syzygy::Wdl) -> Self {
+        match wdl {
+            shakmaty_syzygy::Wdl::Loss => Wdl::Loss,
+            shakmaty_syzygy::Wdl::BlessedLoss => Wdl::BlessedLoss,
+            shakmaty_syzygy::Wdl::Draw => Wdl::Draw,
+            shakmaty_syzygy::Wdl::CursedWin => Wdl::CursedWin,
+            shakmaty_syzygy::Wdl::Win => Wdl::Win,
+        }
+    }
+}
+
+impl Game {
+    /// End the `Game` if its result is known by the tablebase: a win for the side that can
+    /// force it, or a draw otherwise. Returns the `Outcome` if so
+    pub fn adjudicate(&mut self, tablebase: &Tablebase) -> Option<Outcome> {
+        if self.state == GameState::Ended {
+            return None;
+        }
+        let probe = tablebase.probe(self).ok()?;
+        let color = Game::static_playing_color(self.turn);
+        let result = match (probe.wdl, color) {
+            (Wdl::Win, White) | (Wdl::Loss, Black) => GameResult::WhiteWins,
+            (Wdl::Win, Black) | (Wdl::Loss, White) => GameResult::BlackWins,
+            _ => GameResult::Draw,
+        };
+        self.end_with(Outcome {
+            result,
+            reason: EndReason::Tablebase,
+        });
+        self.outcome
+    }
+}
+
+/// Map a probe failure to a `TablebaseError`
+fn probe_error(error: SyzygyError) -> TablebaseError {
+    match error {
+        SyzygyError::Castling => TablebaseError::Castling,
+        error => TablebaseError::Probe(error.to_string()),
+    }
+}
@@ -1,13 +1,23 @@
+#[cfg(feature = "syzygy")]
+use chess_lib::tablebase::{Probe, Tablebase, TablebaseError, Wdl};
 use chess_lib::{
     assert_err, assert_ok,
-    board::{Board, Movement, MovementError, Position},
-    game::Game,
+    board::{Board, Movement, MovementError, PawnRules, Position},
+    book::{BookError, BookSelection, PolyglotBook},
+    engine::{evaluate, Engine, SearchLimits, MATE_SCORE},
+    fen::{FenError, STARTING_FEN},
+    game::{EndReason, Game, GameResult, GameState, Outcome},
+    layout::{Layout, LayoutError},
+    pgn::{parse_pgn, PgnError, PgnTags},
     pieces::{
-        Bishop,
+        Bishop, ChessPiece,
         Color::{self, Black, White},
-        King, Pawn, Piece, PieceFactory, Queen, Rook,
+        King, Knight, Moves, Pawn, Piece, PieceFactory, PieceKind, Promotion, Queen, Rook,
     },
 };
+use std::collections::HashSet;
+#[cfg(feature = "syzygy")]
+use std::fs;
 
 fn create_board() -> Board {
     let dimension = Position { x: 7, y: 7 };
@@ -24,13 +34,14 @@ fn add_pieces_and_move<T: Piece + 'static>(
     let movement = Movement {
         from: init_position,
         to: dest_position,
+        promotion: None,
     };
     let mut pieces = vec![(movement.from, PieceFactory::create::<T>(White))];
     if let Some((blocker_position, blocker_color)) = maybe_blocker {
         pieces.push((blocker_position, PieceFactory::create::<T>(blocker_color)));
     }
 
-    game.board.add_pieces(pieces);
+    game.board.add_pieces(pieces)?;
     game.board.move_piece(White, &movement)
 }
 
@@ -102,14 +113,14 @@ fn horizontal_vertical_movements<T: Piece + 'static, F>(
     min_dimension: i32,
     assertion: F,
 ) where
-    F: Fn(Result<(), MovementError>) -> (),
+    F: Fn(Result<(), MovementError>),
 {
-    (min_dimension..=max_dimension_x).into_iter().for_each(|i| {
+    (min_dimension..=max_dimension_x).for_each(|i| {
         assertion(test_horizontal_right::<T>(game, i, None, true));
         assertion(test_horizontal_left::<T>(game, i, None, true));
     });
 
-    (min_dimension..=max_dimension_y).into_iter().for_each(|i| {
+    (min_dimension..=max_dimension_y).for_each(|i| {
         assertion(test_vertical_forward::<T>(game, i, None, true));
         assertion(test_vertical_backward::<T>(game, i, None, true));
     });
@@ -126,7 +137,7 @@ fn horizontal_vertical_path_blocked<T: Piece + 'static, F, G>(
     F: Fn(Result<(), MovementError>),
     G: Fn(Result<(), MovementError>, &mut Game),
 {
-    (1..=max_dimension_x).into_iter().for_each(|i| {
+    (1..=max_dimension_x).for_each(|i| {
         let blocker = Some((Position { x: i, y: 0 }, blocker_color));
         if i != max_dimension_x {
             assert_blocked_path(test_horizontal_right::<T>(
@@ -144,7 +155,7 @@ fn horizontal_vertical_path_blocked<T: Piece + 'static, F, G>(
         }
     });
 
-    (0..=max_dimension_x - 1).into_iter().for_each(|i| {
+    (0..=max_dimension_x - 1).for_each(|i| {
         let blocker = Some((Position { x: i, y: 0 }, blocker_color));
         if i != 0 {
             assert_blocked_path(test_horizontal_left::<T>(
@@ -162,7 +173,7 @@ fn horizontal_vertical_path_blocked<T: Piece + 'static, F, G>(
         }
     });
 
-    (1..=max_dimension_y).into_iter().for_each(|i| {
+    (1..=max_dimension_y).for_each(|i| {
         let blocker = Some((Position { x: 0, y: i }, blocker_color));
         if i != max_dimension_y {
             assert_blocked_path(test_vertical_forward::<T>(
@@ -180,7 +191,7 @@ fn horizontal_vertical_path_blocked<T: Piece + 'static, F, G>(
         }
     });
 
-    (0..=max_dimension_y - 1).into_iter().for_each(|i| {
+    (0..=max_dimension_y - 1).for_each(|i| {
         let blocker = Some((Position { x: 0, y: i }, blocker_color));
         if i != 0 {
             assert_blocked_path(test_vertical_backward::<T>(
@@ -314,11 +325,11 @@ fn diagonal_movements<T: Piece + 'static, F>(
     min_dimension: i32,
     assertion: F,
 ) where
-    F: Fn(Result<(), MovementError>) -> (),
+    F: Fn(Result<(), MovementError>),
 {
     let dimension = max_dimension_x.min(max_dimension_y);
 
-    (min_dimension..=dimension).into_iter().for_each(|i| {
+    (min_dimension..=dimension).for_each(|i| {
         assertion(test_diagonal_forward_right::<T>(game, i, None, true));
         assertion(test_diagonal_forward_left::<T>(game, i, None, true));
         assertion(test_diagonal_backward_right::<T>(game, i, None, true));
@@ -339,7 +350,7 @@ fn diagonal_path_blocked<T: Piece + 'static, F, G>(
 {
     let max_dimension = max_dimension_x.min(max_dimension_y);
 
-    (1..=max_dimension).into_iter().for_each(|i| {
+    (1..=max_dimension).for_each(|i| {
         let blocker_position = Some((Position { x: i, y: i }, blocker_color));
         if i != max_dimension {
             assert_blocked_path(test_diagonal_forward_right::<T>(
@@ -357,7 +368,7 @@ fn diagonal_path_blocked<T: Piece + 'static, F, G>(
         }
     });
 
-    (1..=max_dimension).into_iter().for_each(|i| {
+    (1..=max_dimension).for_each(|i| {
         let blocker_position = Some((
             Position {
                 x: max_dimension - i,
@@ -381,7 +392,7 @@ fn diagonal_path_blocked<T: Piece + 'static, F, G>(
         }
     });
 
-    (1..=max_dimension).into_iter().for_each(|i| {
+    (1..=max_dimension).for_each(|i| {
         let blocker_position = Some((
             Position {
                 x: max_dimension - i,
@@ -405,7 +416,7 @@ fn diagonal_path_blocked<T: Piece + 'static, F, G>(
         }
     });
 
-    (1..=max_dimension).into_iter().for_each(|i| {
+    (1..=max_dimension).for_each(|i| {
         let blocker_position = Some((
             Position {
                 x: i,
@@ -489,13 +500,14 @@ fn test_pawn_movements() {
     let pawn_a = (initial_position_a, PieceFactory::create::<Pawn>(White));
     let pawn_b = (initial_position_b, PieceFactory::create::<Pawn>(White));
 
-    game.board.add_pieces(vec![pawn_a, pawn_b]);
+    assert_ok!(game.board.add_pieces(vec![pawn_a, pawn_b]));
 
     // Test Forward(1)
     let mut end_position = Position { x: 0, y: 2 };
     let mut movement = Movement {
         from: initial_position_a,
         to: end_position,
+        promotion: None,
     };
     assert_ok!(game.board.move_piece(White, &movement));
 
@@ -504,6 +516,7 @@ fn test_pawn_movements() {
     movement = Movement {
         from: initial_position_b,
         to: end_position,
+        promotion: None,
     };
     assert_ok!(game.board.move_piece(White, &movement));
 }
@@ -709,3 +722,2138 @@ fn king_movements_path_blocked() {
     );
     diagonal_path_blocked_for_both_colors::<King>(&mut game, max_dimension_x, max_dimension_y);
 }
+
+fn castling_game(extra_pieces: Vec<(Position, Box<dyn Piece>)>) -> Game {
+    let mut game = Game::new(create_board());
+    let mut pieces = vec![
+        (Position { x: 4, y: 0 }, PieceFactory::create::<King>(White)),
+        (Position { x: 0, y: 0 }, PieceFactory::create::<Rook>(White)),
+        (Position { x: 7, y: 0 }, PieceFactory::create::<Rook>(White)),
+    ];
+    pieces.extend(extra_pieces);
+    assert_ok!(game.board.add_pieces(pieces));
+    game
+}
+
+fn is_piece<T: Piece + 'static>(game: &Game, position: Position) -> bool {
+    game.board
+        .positions
+        .get(&position)
+        .is_some_and(|piece| piece.kind == T::new(piece.color).kind())
+}
+
+#[test]
+fn castling_moves_king_and_rook() {
+    // King side
+    let mut game = castling_game(vec![]);
+    assert_ok!(game.move_piece("e1g1".to_string()));
+    assert!(is_piece::<King>(&game, Position { x: 6, y: 0 }));
+    assert!(is_piece::<Rook>(&game, Position { x: 5, y: 0 }));
+    assert!(!game.board.positions.contains_key(&Position { x: 7, y: 0 }));
+    assert!(!game.board.castling_rights(White).king_side);
+    assert!(!game.board.castling_rights(White).queen_side);
+
+    // Queen side
+    let mut game = castling_game(vec![]);
+    assert_ok!(game.move_piece("e1c1".to_string()));
+    assert!(is_piece::<King>(&game, Position { x: 2, y: 0 }));
+    assert!(is_piece::<Rook>(&game, Position { x: 3, y: 0 }));
+    assert!(!game.board.positions.contains_key(&Position { x: 0, y: 0 }));
+}
+
+#[test]
+fn castling_blocked_path() {
+    let mut game = castling_game(vec![(
+        Position { x: 1, y: 0 },
+        PieceFactory::create::<Bishop>(White),
+    )]);
+    assert_err!(
+        game.move_piece("e1c1".to_string()),
+        MovementError::BlockedPath
+    );
+    assert_ok!(game.move_piece("e1g1".to_string()));
+}
+
+#[test]
+fn castling_not_allowed_after_moving() {
+    let mut game = castling_game(vec![]);
+    let rook_away = Movement {
+        from: Position { x: 7, y: 0 },
+        to: Position { x: 7, y: 1 },
+        promotion: None,
+    };
+    let rook_back = Movement {
+        from: Position { x: 7, y: 1 },
+        to: Position { x: 7, y: 0 },
+        promotion: None,
+    };
+    assert_ok!(game.board.move_piece(White, &rook_away));
+    assert_ok!(game.board.move_piece(White, &rook_back));
+    assert!(!game.board.castling_rights(White).king_side);
+    assert!(game.board.castling_rights(White).queen_side);
+    assert_err!(
+        game.move_piece("e1g1".to_string()),
+        MovementError::IllegalMovement
+    );
+}
+
+#[test]
+fn castling_not_allowed_through_check() {
+    // King is in check
+    let mut game = castling_game(vec![(
+        Position { x: 4, y: 7 },
+        PieceFactory::create::<Rook>(Black),
+    )]);
+    assert_err!(game.move_piece("e1g1".to_string()), MovementError::Check);
+
+    // King crosses an attacked square
+    let mut game = castling_game(vec![(
+        Position { x: 5, y: 7 },
+        PieceFactory::create::<Rook>(Black),
+    )]);
+    assert_err!(game.move_piece("e1g1".to_string()), MovementError::Check);
+    assert_ok!(game.move_piece("e1c1".to_string()));
+
+    // King ends in check
+    let mut game = castling_game(vec![(
+        Position { x: 2, y: 7 },
+        PieceFactory::create::<Rook>(Black),
+    )]);
+    assert_err!(game.move_piece("e1c1".to_string()), MovementError::Check);
+
+    // Only the rook crosses an attacked square
+    let mut game = castling_game(vec![(
+        Position { x: 1, y: 7 },
+        PieceFactory::create::<Rook>(Black),
+    )]);
+    assert_ok!(game.move_piece("e1c1".to_string()));
+}
+
+fn en_passant_game() -> Game {
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 4 }, PieceFactory::create::<Pawn>(White)),
+        (Position { x: 3, y: 6 }, PieceFactory::create::<Pawn>(Black)),
+        (Position { x: 0, y: 1 }, PieceFactory::create::<Pawn>(White)),
+        (Position { x: 7, y: 6 }, PieceFactory::create::<Pawn>(Black)),
+    ]));
+    // Black to move
+    game.new_turn();
+    game
+}
+
+#[test]
+fn en_passant_capture() {
+    let mut game = en_passant_game();
+    assert_ok!(game.move_piece("d7d5".to_string()));
+    assert_eq!(game.board.en_passant, Some(Position { x: 3, y: 5 }));
+
+    assert_ok!(game.move_piece("e5d6".to_string()));
+    assert_eq!(game.board.en_passant, None);
+    assert!(is_piece::<Pawn>(&game, Position { x: 3, y: 5 }));
+    assert!(!game.board.positions.contains_key(&Position { x: 3, y: 4 }));
+    assert!(!game
+        .board
+        .pieces_set
+        .get(&Black)
+        .expect("Color exists")
+        .contains(&Position { x: 3, y: 4 }));
+    assert_eq!(game.board.dead_pieces(Black).len(), 1);
+    assert!(game.board.dead_pieces(Black)[0]
+        .as_any()
+        .downcast_ref::<Pawn>()
+        .is_some());
+}
+
+#[test]
+fn en_passant_only_right_after_double_step() {
+    let mut game = en_passant_game();
+    assert_ok!(game.move_piece("d7d5".to_string()));
+    assert_ok!(game.move_piece("a2a3".to_string()));
+    assert_eq!(game.board.en_passant, None);
+    assert_ok!(game.move_piece("h7h6".to_string()));
+
+    let board = game.board.clone();
+    assert!(game.move_piece("e5d6".to_string()).is_err());
+    assert_eq!(game.board, board);
+    assert!(is_piece::<Pawn>(&game, Position { x: 3, y: 4 }));
+    assert!(game.board.dead_pieces(Black).is_empty());
+}
+
+fn promotion_game() -> Game {
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 0, y: 6 }, PieceFactory::create::<Pawn>(White)),
+        (Position { x: 2, y: 5 }, PieceFactory::create::<Pawn>(White)),
+        (Position { x: 1, y: 7 }, PieceFactory::create::<Rook>(Black)),
+        (Position { x: 7, y: 1 }, PieceFactory::create::<Pawn>(Black)),
+    ]));
+    game
+}
+
+#[test]
+fn pawn_promotion() {
+    let mut game = promotion_game();
+    assert_ok!(game.move_piece("a7a8q".to_string()));
+    assert!(is_piece::<Queen>(&game, Position { x: 0, y: 7 }));
+    assert!(!game.board.positions.contains_key(&Position { x: 0, y: 6 }));
+
+    assert_ok!(game.move_piece("h2h1R".to_string()));
+    assert!(is_piece::<Rook>(&game, Position { x: 7, y: 0 }));
+    assert_eq!(
+        game.board
+            .positions
+            .get(&Position { x: 7, y: 0 })
+            .expect("Piece exists")
+            .color,
+        Black
+    );
+
+    // Promotion capturing a piece
+    let mut game = promotion_game();
+    assert_ok!(game.move_piece("a7b8n".to_string()));
+    assert!(is_piece::<Knight>(&game, Position { x: 1, y: 7 }));
+    assert_eq!(game.board.dead_pieces(Black).len(), 1);
+}
+
+#[test]
+fn pawn_promotion_required_only_in_last_row() {
+    let mut game = promotion_game();
+    assert_err!(
+        game.move_piece("a7a8".to_string()),
+        MovementError::InvalidPromotion
+    );
+    assert_err!(
+        game.move_piece("c6c7q".to_string()),
+        MovementError::InvalidPromotion
+    );
+    assert_err!(
+        game.move_piece("a7a8k".to_string()),
+        MovementError::WrongCommand("a7a8k".to_string())
+    );
+    assert!(is_piece::<Pawn>(&game, Position { x: 0, y: 6 }));
+    assert!(is_piece::<Pawn>(&game, Position { x: 2, y: 5 }));
+}
+
+#[test]
+fn pawn_double_step_only_from_start_row() {
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 3 }, PieceFactory::create::<Pawn>(White)),
+        (Position { x: 3, y: 1 }, PieceFactory::create::<Pawn>(White)),
+        (Position { x: 2, y: 1 }, PieceFactory::create::<Pawn>(White)),
+        (
+            Position { x: 2, y: 2 },
+            PieceFactory::create::<Knight>(Black),
+        ),
+        (Position { x: 3, y: 6 }, PieceFactory::create::<Pawn>(Black)),
+    ]));
+
+    assert_err!(
+        game.move_piece("e4e6".to_string()),
+        MovementError::IllegalMovement
+    );
+    // Path is blocked
+    assert_err!(
+        game.move_piece("c2c4".to_string()),
+        MovementError::BlockedPath
+    );
+    assert_ok!(game.move_piece("d2d4".to_string()));
+    assert_ok!(game.move_piece("d7d5".to_string()));
+}
+
+#[test]
+fn pawn_captures_only_diagonally() {
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 3 }, PieceFactory::create::<Pawn>(White)),
+        (Position { x: 4, y: 4 }, PieceFactory::create::<Pawn>(Black)),
+        (
+            Position { x: 3, y: 4 },
+            PieceFactory::create::<Knight>(Black),
+        ),
+    ]));
+
+    // Forward movements can not capture
+    assert_err!(
+        game.move_piece("e4e5".to_string()),
+        MovementError::BlockedPath
+    );
+    // Diagonal movements must capture
+    assert_err!(
+        game.move_piece("e4f5".to_string()),
+        MovementError::IllegalMovement
+    );
+    // Backward captures are not allowed
+    let backward = Movement {
+        from: Position { x: 4, y: 3 },
+        to: Position { x: 3, y: 2 },
+        promotion: None,
+    };
+    assert_err!(
+        game.board.move_piece(White, &backward),
+        MovementError::IllegalMovement
+    );
+    assert_ok!(game.move_piece("e4d5".to_string()));
+    assert_eq!(game.board.dead_pieces(Black).len(), 1);
+}
+
+#[test]
+fn pinned_piece_can_not_move() {
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 0 }, PieceFactory::create::<King>(White)),
+        (
+            Position { x: 4, y: 1 },
+            PieceFactory::create::<Bishop>(White),
+        ),
+        (Position { x: 4, y: 7 }, PieceFactory::create::<Rook>(Black)),
+    ]));
+
+    assert_err!(game.move_piece("e2d3".to_string()), MovementError::Check);
+    // Board is left untouched
+    assert!(is_piece::<Bishop>(&game, Position { x: 4, y: 1 }));
+    assert!(!game.board.positions.contains_key(&Position { x: 3, y: 2 }));
+    assert!(!game.is_in_check());
+}
+
+#[test]
+fn king_can_not_move_into_check() {
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 0 }, PieceFactory::create::<King>(White)),
+        (Position { x: 3, y: 7 }, PieceFactory::create::<Rook>(Black)),
+        (Position { x: 6, y: 2 }, PieceFactory::create::<Pawn>(Black)),
+    ]));
+
+    assert_err!(game.move_piece("e1d1".to_string()), MovementError::Check);
+    assert_err!(game.move_piece("e1f2".to_string()), MovementError::Check);
+    assert!(game.board.is_attacked(Position { x: 5, y: 1 }, Black));
+    assert!(!game.board.is_attacked(Position { x: 6, y: 1 }, Black));
+    assert_ok!(game.move_piece("e1f1".to_string()));
+}
+
+#[test]
+fn check_must_be_answered() {
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 0 }, PieceFactory::create::<King>(White)),
+        (Position { x: 0, y: 1 }, PieceFactory::create::<Pawn>(White)),
+        (Position { x: 0, y: 3 }, PieceFactory::create::<Rook>(White)),
+        (
+            Position { x: 4, y: 7 },
+            PieceFactory::create::<Queen>(Black),
+        ),
+        (
+            Position { x: 0, y: 7 },
+            PieceFactory::create::<Knight>(Black),
+        ),
+    ]));
+    assert!(game.is_in_check());
+    assert!(game.board.is_in_check(White));
+    assert!(!game.board.is_in_check(Black));
+
+    assert_err!(game.move_piece("a2a3".to_string()), MovementError::Check);
+    // A rejected capture restores the captured piece
+    assert_err!(game.move_piece("a4a8".to_string()), MovementError::Check);
+    assert!(is_piece::<Knight>(&game, Position { x: 0, y: 7 }));
+    assert!(game
+        .board
+        .pieces_set
+        .get(&Black)
+        .expect("Color exists")
+        .contains(&Position { x: 0, y: 7 }));
+    assert!(game.board.dead_pieces(Black).is_empty());
+
+    assert_ok!(game.move_piece("a4e4".to_string()));
+    assert!(!game.board.is_in_check(White));
+}
+
+fn standard_game() -> Game {
+    let mut game = Game::new(create_board());
+    game.set_board();
+    game.start();
+    game
+}
+
+#[test]
+fn checkmate_ends_the_game() {
+    let mut game = standard_game();
+    for movement in ["f2f3", "e7e5", "g2g4"] {
+        assert_ok!(game.move_piece(movement.to_string()));
+        assert!(game.is_ongoing());
+    }
+    assert_ok!(game.move_piece("d8h4".to_string()));
+
+    assert_eq!(game.state, GameState::Ended);
+    assert_eq!(
+        game.outcome,
+        Some(Outcome {
+            result: GameResult::BlackWins,
+            reason: EndReason::Checkmate,
+        })
+    );
+    assert_err!(
+        game.move_piece("a2a3".to_string()),
+        MovementError::GameEnded
+    );
+}
+
+#[test]
+fn stalemate_ends_the_game() {
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 5, y: 6 }, PieceFactory::create::<King>(White)),
+        (
+            Position { x: 6, y: 4 },
+            PieceFactory::create::<Queen>(White),
+        ),
+        (Position { x: 7, y: 7 }, PieceFactory::create::<King>(Black)),
+    ]));
+    game.start();
+    assert_ok!(game.move_piece("g5g6".to_string()));
+
+    assert_eq!(game.state, GameState::Ended);
+    assert_eq!(
+        game.outcome,
+        Some(Outcome {
+            result: GameResult::Draw,
+            reason: EndReason::Stalemate,
+        })
+    );
+}
+
+/// Legal movements found trying every origin and destination pair through `move_piece`
+fn brute_force_legal_moves(game: &mut Game, color: Color) -> HashSet<Movement> {
+    let dimension = game.board.dimension;
+    let squares: Vec<Position> = (0..=dimension.x)
+        .flat_map(|x| (0..=dimension.y).map(move |y| Position { x, y }))
+        .collect();
+    let promotions = [
+        None,
+        Some(Promotion::Queen),
+        Some(Promotion::Rook),
+        Some(Promotion::Bishop),
+        Some(Promotion::Knight),
+    ];
+    let mut moves = HashSet::new();
+
+    for from in squares.iter() {
+        for to in squares.iter() {
+            for promotion in promotions {
+                let movement = Movement {
+                    from: *from,
+                    to: *to,
+                    promotion,
+                };
+                // Apply every movement in a fresh copy of the position
+                let mut copy = game.board.clone();
+                if copy.move_piece(color, &movement).is_ok() {
+                    moves.insert(movement);
+                }
+            }
+        }
+    }
+    moves
+}
+
+#[test]
+fn legal_moves_in_initial_position() {
+    let mut game = standard_game();
+    assert_eq!(game.legal_moves().len(), 20);
+    assert_eq!(game.board.legal_moves(Black).len(), 20);
+
+    let pawn_moves: HashSet<Position> = game
+        .board
+        .legal_moves_from(Position { x: 4, y: 1 })
+        .iter()
+        .map(|movement| movement.to)
+        .collect();
+    assert_eq!(
+        pawn_moves,
+        HashSet::from([Position { x: 4, y: 2 }, Position { x: 4, y: 3 }])
+    );
+    assert!(game
+        .board
+        .legal_moves_from(Position { x: 4, y: 4 })
+        .is_empty());
+}
+
+#[test]
+fn legal_moves_match_brute_force() {
+    // Castling, en passant, promotions, pins and checks
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 0 }, PieceFactory::create::<King>(White)),
+        (Position { x: 0, y: 0 }, PieceFactory::create::<Rook>(White)),
+        (Position { x: 7, y: 0 }, PieceFactory::create::<Rook>(White)),
+        (Position { x: 4, y: 4 }, PieceFactory::create::<Pawn>(White)),
+        (Position { x: 1, y: 6 }, PieceFactory::create::<Pawn>(White)),
+        (
+            Position { x: 3, y: 1 },
+            PieceFactory::create::<Knight>(White),
+        ),
+        (
+            Position { x: 6, y: 1 },
+            PieceFactory::create::<Bishop>(White),
+        ),
+        (Position { x: 6, y: 7 }, PieceFactory::create::<King>(Black)),
+        (Position { x: 3, y: 6 }, PieceFactory::create::<Pawn>(Black)),
+        (Position { x: 0, y: 7 }, PieceFactory::create::<Rook>(Black)),
+        (
+            Position { x: 0, y: 4 },
+            PieceFactory::create::<Bishop>(Black),
+        ),
+        (
+            Position { x: 7, y: 5 },
+            PieceFactory::create::<Queen>(Black),
+        ),
+    ]));
+    game.new_turn();
+    assert_ok!(game.move_piece("d7d5".to_string()));
+
+    for color in [White, Black] {
+        let legal_moves: HashSet<Movement> = game.board.legal_moves(color).into_iter().collect();
+        assert_eq!(legal_moves, brute_force_legal_moves(&mut game, color));
+    }
+    // Knight is pinned, en passant, castling and promotions are available
+    assert!(game
+        .board
+        .legal_moves_from(Position { x: 3, y: 1 })
+        .is_empty());
+    let white_moves = game.board.legal_moves(White);
+    for (from, to) in [((4, 4), (3, 5)), ((4, 0), (6, 0)), ((4, 0), (2, 0))] {
+        assert!(white_moves.contains(&Movement {
+            from: Position {
+                x: from.0,
+                y: from.1
+            },
+            to: Position { x: to.0, y: to.1 },
+            promotion: None,
+        }));
+    }
+    assert_eq!(
+        white_moves
+            .iter()
+            .filter(|movement| movement.promotion.is_some())
+            .count(),
+        8
+    );
+}
+
+fn play(game: &mut Game, movements: &[&str]) {
+    for movement in movements {
+        assert_ok!(game.move_piece(movement.to_string()));
+    }
+}
+
+#[test]
+fn threefold_and_fivefold_repetition() {
+    let knight_shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+    let mut game = standard_game();
+    play(&mut game, &knight_shuffle);
+    assert_eq!(game.claimable_draw(), None);
+    assert_eq!(game.claim_draw(), None);
+
+    // Initial position occurs for the third time
+    play(&mut game, &knight_shuffle);
+    assert_eq!(game.claimable_draw(), Some(EndReason::ThreefoldRepetition));
+    assert!(game.is_ongoing());
+
+    // Fifth time ends the game
+    play(&mut game, &knight_shuffle);
+    play(&mut game, &knight_shuffle[..3]);
+    assert!(game.is_ongoing());
+    play(&mut game, &knight_shuffle[3..]);
+    assert_eq!(
+        game.outcome,
+        Some(Outcome {
+            result: GameResult::Draw,
+            reason: EndReason::FivefoldRepetition,
+        })
+    );
+
+    // Claiming a threefold repetition
+    let mut game = standard_game();
+    play(&mut game, &knight_shuffle);
+    play(&mut game, &knight_shuffle);
+    assert_eq!(
+        game.claim_draw(),
+        Some(Outcome {
+            result: GameResult::Draw,
+            reason: EndReason::ThreefoldRepetition,
+        })
+    );
+    assert_eq!(game.state, GameState::Ended);
+}
+
+#[test]
+fn fifty_and_seventy_five_move_rules() {
+    let mut game = standard_game();
+    game.halfmove_clock = 98;
+    play(&mut game, &["g1f3"]);
+    assert_eq!(game.claimable_draw(), None);
+    play(&mut game, &["g8f6"]);
+    assert_eq!(game.claimable_draw(), Some(EndReason::FiftyMoveRule));
+
+    // Pawn movements reset the clock
+    play(&mut game, &["e2e4"]);
+    assert_eq!(game.halfmove_clock, 0);
+    assert_eq!(game.claimable_draw(), None);
+
+    game.halfmove_clock = 149;
+    play(&mut game, &["b8c6"]);
+    assert_eq!(
+        game.outcome,
+        Some(Outcome {
+            result: GameResult::Draw,
+            reason: EndReason::SeventyFiveMoveRule,
+        })
+    );
+}
+
+#[test]
+fn insufficient_material() {
+    // King and Bishop against King after capturing the last Pawn
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 0 }, PieceFactory::create::<King>(White)),
+        (
+            Position { x: 2, y: 2 },
+            PieceFactory::create::<Bishop>(White),
+        ),
+        (Position { x: 4, y: 7 }, PieceFactory::create::<King>(Black)),
+        (Position { x: 6, y: 6 }, PieceFactory::create::<Pawn>(Black)),
+    ]));
+    assert!(!game.board.has_insufficient_material());
+    play(&mut game, &["c3g7"]);
+    assert_eq!(
+        game.outcome,
+        Some(Outcome {
+            result: GameResult::Draw,
+            reason: EndReason::InsufficientMaterial,
+        })
+    );
+
+    // King and Knight against King
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 0 }, PieceFactory::create::<King>(White)),
+        (Position { x: 4, y: 7 }, PieceFactory::create::<King>(Black)),
+        (
+            Position { x: 1, y: 7 },
+            PieceFactory::create::<Knight>(Black),
+        ),
+    ]));
+    assert!(game.board.has_insufficient_material());
+
+    // Bishops on squares of different colors can still checkmate
+    assert_ok!(game.board.add_pieces(vec![
+        (
+            Position { x: 2, y: 0 },
+            PieceFactory::create::<Bishop>(White),
+        ),
+        (
+            Position { x: 5, y: 0 },
+            PieceFactory::create::<Bishop>(White),
+        ),
+    ]));
+    assert!(!game.board.has_insufficient_material());
+}
+
+#[test]
+fn fen_round_trip() {
+    let game = standard_game();
+    assert_eq!(game.to_fen(), STARTING_FEN);
+
+    for fen in [
+        STARTING_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+        "4k3/8/8/8/8/8/8/4K2R w K - 0 1",
+    ] {
+        assert_eq!(Game::from_fen(fen).expect("Valid FEN").to_fen(), fen);
+    }
+}
+
+#[test]
+fn fen_import_sets_up_the_position() {
+    let mut game = Game::from_fen("rnbqkbnr/pp1ppppp/8/8/2pPP3/8/PPP2PPP/RNBQKBNR b Kq d3 0 3")
+        .expect("Valid FEN");
+    assert_eq!(game.turn, 5);
+    assert_eq!(game.board.en_passant, Some(Position { x: 3, y: 2 }));
+    assert!(game.board.castling_rights(White).king_side);
+    assert!(!game.board.castling_rights(White).queen_side);
+    assert!(!game.board.castling_rights(Black).king_side);
+    assert!(game.board.castling_rights(Black).queen_side);
+    assert!(is_piece::<Pawn>(&game, Position { x: 2, y: 3 }));
+
+    // Black can capture en passant
+    play(&mut game, &["c4d3"]);
+    assert!(!game.board.positions.contains_key(&Position { x: 3, y: 3 }));
+    assert_eq!(
+        game.to_fen(),
+        "rnbqkbnr/pp1ppppp/8/8/4P3/3p4/PPP2PPP/RNBQKBNR w Kq - 0 4"
+    );
+
+    // Halfmove clock and fullmove number are optional
+    let game = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - -").expect("Valid FEN");
+    assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+
+    // Other board dimensions
+    let game = Game::from_fen("2k2/5/5/5/2K2 w - - 0 1").expect("Valid FEN");
+    assert_eq!(game.board.dimension, Position { x: 4, y: 4 });
+}
+
+#[test]
+fn fen_parse_errors() {
+    assert_eq!(
+        Game::from_fen("8/8/8/8/8/8/8/8 w").err(),
+        Some(FenError::WrongFieldsNumber(2))
+    );
+    assert_eq!(
+        FenError::WrongFieldsNumber(2).to_string(),
+        "FEN must have 4 to 6 fields, found 2"
+    );
+    assert_eq!(
+        Game::from_fen("rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").err(),
+        Some(FenError::InvalidPlacement(
+            "rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR".to_string()
+        ))
+    );
+    assert_eq!(
+        Game::from_fen("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").err(),
+        Some(FenError::InvalidPlacement(
+            "rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR".to_string()
+        ))
+    );
+    assert_eq!(
+        Game::from_fen("4k3/8/8/8/8/8/8/4K3 x - - 0 1").err(),
+        Some(FenError::InvalidSideToMove("x".to_string()))
+    );
+    assert_eq!(
+        Game::from_fen("4k3/8/8/8/8/8/8/4K3 w KK - 0 1").err(),
+        Some(FenError::InvalidCastling("KK".to_string()))
+    );
+    assert_eq!(
+        Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - z9 0 1").err(),
+        Some(FenError::InvalidEnPassant("z9".to_string()))
+    );
+    // The en passant square must be right behind an enemy `Pawn` that has just double stepped
+    assert!(Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").is_ok());
+    assert!(Game::from_fen("4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1").is_ok());
+    for (fen, square) in [
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d3 0 1", "d3"),
+        ("4k3/8/8/3pP3/8/8/8/4K3 b - d6 0 1", "d6"),
+        ("4k3/8/8/4P3/8/8/8/4K3 w - d6 0 1", "d6"),
+        ("4k3/8/8/3PP3/8/8/8/4K3 w - d6 0 1", "d6"),
+        ("4k3/3p4/8/3pP3/8/8/8/4K3 w - d6 0 1", "d6"),
+        ("4k3/8/3n4/3pP3/8/8/8/4K3 w - d6 0 1", "d6"),
+    ] {
+        assert_eq!(
+            Game::from_fen(fen).err(),
+            Some(FenError::InvalidEnPassant(square.to_string()))
+        );
+    }
+    assert_eq!(
+        Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - -1 1").err(),
+        Some(FenError::InvalidHalfmoveClock("-1".to_string()))
+    );
+    assert_eq!(
+        Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 0").err(),
+        Some(FenError::InvalidFullmoveNumber("0".to_string()))
+    );
+    // Numbers overflowing the `Game` turn or the `Board` files are rejected, not wrapped
+    assert_eq!(
+        Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 4294967295").err(),
+        Some(FenError::InvalidFullmoveNumber("4294967295".to_string()))
+    );
+    assert_eq!(
+        Game::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 2147483649").err(),
+        Some(FenError::InvalidFullmoveNumber("2147483649".to_string()))
+    );
+    assert_eq!(
+        Game::from_fen("K2147483646k w - - 0 1").err(),
+        Some(FenError::InvalidPlacement("K2147483646k".to_string()))
+    );
+    assert_eq!(
+        Game::from_fen("K99999999999k w - - 0 1").err(),
+        Some(FenError::InvalidPlacement("K99999999999k".to_string()))
+    );
+    assert_eq!(
+        Game::from_fen("K2147483645k w - - 0 1").err(),
+        Some(FenError::InvalidPlacement("K2147483645k".to_string()))
+    );
+    assert!(Game::from_fen("K24k w - - 0 1").is_ok());
+    assert_eq!(
+        Game::from_fen("K25k w - - 0 1").err(),
+        Some(FenError::InvalidPlacement("K25k".to_string()))
+    );
+}
+
+fn sans(game: &Game) -> Vec<&str> {
+    game.history()
+        .iter()
+        .map(|played| played.san.as_str())
+        .collect()
+}
+
+#[test]
+fn san_movements() {
+    let mut game = standard_game();
+    play(
+        &mut game,
+        &[
+            "e4", "Nf6", "e5", "d5", "exd6", "e6", "Nf3", "Bxd6", "Bb5+", "c6", "O-O", "O-O",
+        ],
+    );
+    assert_eq!(
+        sans(&game),
+        vec!["e4", "Nf6", "e5", "d5", "exd6", "e6", "Nf3", "Bxd6", "Bb5+", "c6", "O-O", "O-O"]
+    );
+    assert_eq!(game.last_move(), Some("O-O"));
+    assert!(is_piece::<King>(&game, Position { x: 6, y: 0 }));
+    assert!(is_piece::<Rook>(&game, Position { x: 5, y: 7 }));
+
+    // Coordinates are still accepted and rendered as SAN
+    play(&mut game, &["b5c6"]);
+    assert_eq!(game.last_move(), Some("Bxc6"));
+
+    // Errors are those of the notation of the command, coordinates or SAN
+    let mut game = standard_game();
+    assert_err!(
+        game.move_piece("e2e9".to_string()),
+        MovementError::OutOfBounds
+    );
+    assert_err!(
+        game.move_piece("e7e5".to_string()),
+        MovementError::WrongPiece(Black)
+    );
+    assert_err!(
+        game.move_piece("d1d3".to_string()),
+        MovementError::BlockedPath
+    );
+    assert_err!(
+        game.move_piece("Qd3".to_string()),
+        MovementError::IllegalMovement
+    );
+    assert_err!(
+        game.move_piece("e2-e4".to_string()),
+        MovementError::WrongCommand("e2-e4".to_string())
+    );
+    assert!(game.history().is_empty());
+
+    // Checkmate suffix
+    let mut game = standard_game();
+    play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+    assert_eq!(game.last_move(), Some("Qh4#"));
+    assert_eq!(
+        game.outcome.map(|outcome| outcome.reason),
+        Some(EndReason::Checkmate)
+    );
+}
+
+#[test]
+fn san_disambiguation_and_promotion() {
+    let mut game = Game::from_fen("4k3/P7/8/8/R7/8/8/RN1NK3 w - - 0 1").expect("Valid FEN");
+    let movement = |from: Position, to: Position, promotion: Option<Promotion>| Movement {
+        from,
+        to,
+        promotion,
+    };
+    let a1 = Position { x: 0, y: 0 };
+    let a3 = Position { x: 0, y: 2 };
+    let a4 = Position { x: 0, y: 3 };
+    let b1 = Position { x: 1, y: 0 };
+    let c3 = Position { x: 2, y: 2 };
+    let d1 = Position { x: 3, y: 0 };
+    let a7 = Position { x: 0, y: 6 };
+    let a8 = Position { x: 0, y: 7 };
+
+    // By file, by rank
+    assert_ok!(game.parse_san("Nbc3"), movement(b1, c3, None));
+    assert_ok!(game.parse_san("Ndxc3"), movement(d1, c3, None));
+    assert_ok!(game.parse_san("R1a3"), movement(a1, a3, None));
+    assert_ok!(game.parse_san("R4a3"), movement(a4, a3, None));
+    assert_ok!(game.to_san(&movement(b1, c3, None)), "Nbc3".to_string());
+    assert_ok!(game.to_san(&movement(a4, a3, None)), "R4a3".to_string());
+
+    // By square
+    let mut queens = Game::from_fen("7k/8/8/8/Q1Q5/8/Q7/4K3 w - - 0 1").expect("Valid FEN");
+    let b3 = Position { x: 1, y: 2 };
+    assert_ok!(
+        queens.to_san(&movement(Position { x: 0, y: 3 }, b3, None)),
+        "Qa4b3".to_string()
+    );
+    assert_ok!(
+        queens.to_san(&movement(Position { x: 2, y: 3 }, b3, None)),
+        "Qcb3".to_string()
+    );
+    assert_ok!(
+        queens.to_san(&movement(Position { x: 0, y: 1 }, b3, None)),
+        "Q2b3".to_string()
+    );
+    assert_ok!(
+        queens.parse_san("Qa4b3"),
+        movement(Position { x: 0, y: 3 }, b3, None)
+    );
+
+    // Promotion, with and without check
+    assert_ok!(
+        game.parse_san("a8=Q+"),
+        movement(a7, a8, Some(Promotion::Queen))
+    );
+    assert_ok!(
+        game.parse_san("a8N"),
+        movement(a7, a8, Some(Promotion::Knight))
+    );
+    assert_ok!(
+        game.to_san(&movement(a7, a8, Some(Promotion::Rook))),
+        "a8=R+".to_string()
+    );
+    assert_ok!(
+        game.to_san(&movement(a7, a8, Some(Promotion::Knight))),
+        "a8=N".to_string()
+    );
+
+    // Errors
+    assert_err!(
+        game.parse_san("Nc3"),
+        MovementError::AmbiguousMovement("Nc3".to_string())
+    );
+    assert_err!(game.parse_san("Nc4"), MovementError::IllegalMovement);
+    assert_err!(game.parse_san("a8"), MovementError::IllegalMovement);
+    assert_err!(game.parse_san("O-O"), MovementError::IllegalMovement);
+    assert_err!(
+        game.parse_san("Zz9"),
+        MovementError::WrongCommand("Zz9".to_string())
+    );
+    assert_err!(
+        game.to_san(&movement(b1, Position { x: 1, y: 3 }, None)),
+        MovementError::IllegalMovement
+    );
+}
+
+#[test]
+fn pgn_export() {
+    let tags = PgnTags {
+        event: "Casual \"blitz\"".to_string(),
+        white: "Alice".to_string(),
+        black: "Bob".to_string(),
+        ..Default::default()
+    };
+
+    // In progress
+    let mut game = standard_game();
+    play(&mut game, &["e4", "e5", "Nf3"]);
+    assert_eq!(
+        game.to_pgn(&tags),
+        "[Event \"Casual \\\"blitz\\\"\"]\n\
+         [Site \"?\"]\n\
+         [Date \"????.??.??\"]\n\
+         [Round \"?\"]\n\
+         [White \"Alice\"]\n\
+         [Black \"Bob\"]\n\
+         [Result \"*\"]\n\
+         \n\
+         1. e4 e5 2. Nf3 *\n"
+    );
+
+    // Finished
+    let mut game = standard_game();
+    play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+    assert_eq!(game.pgn_result(), "0-1");
+    assert!(game
+        .to_pgn(&PgnTags::default())
+        .ends_with("[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+
+    // From a FEN with Black to move
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 12";
+    let mut game = Game::from_fen(fen).expect("Valid FEN");
+    play(&mut game, &["Kd7", "e4"]);
+    let pgn = game.to_pgn(&PgnTags::default());
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n"));
+    assert!(pgn.ends_with("\n12... Kd7 13. e4 *\n"));
+
+    // From a custom `Board`, the FEN of the initial position is derived
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 0 }, PieceFactory::create::<King>(White)),
+        (Position { x: 0, y: 0 }, PieceFactory::create::<Rook>(White)),
+        (Position { x: 4, y: 7 }, PieceFactory::create::<King>(Black)),
+    ]));
+    game.start();
+    play(&mut game, &["Ra7", "Kf8", "Kd2"]);
+    let pgn = game.to_pgn(&PgnTags::default());
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 w Q - 0 1\"]\n"));
+    let replayed = Game::from_pgn(&pgn).expect("Valid PGN");
+    assert_eq!(replayed.to_fen(), game.to_fen());
+
+    // Long movetext is wrapped
+    let mut game = standard_game();
+    for _ in 0..4 {
+        play(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+    }
+    assert_eq!(game.pgn_result(), "1/2-1/2");
+    let pgn = game.to_pgn(&PgnTags::default());
+    let movetext = pgn.split("\n\n").nth(1).expect("There is movetext");
+    assert!(movetext.lines().count() > 1);
+    assert!(movetext.lines().all(|line| line.len() < 80));
+}
+
+#[test]
+fn pgn_import() {
+    let pgn = r#"% Exported by a database
+[Event "Casual \"blitz\""]
+[Site "?"]
+[Result "1-0"]
+
+1. e4 {Best by test} e5 $1 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6
+3.Bb5 a6 ; The Morphy defence
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 1-0
+
+[Event "Mate"]
+[SetUp "1"]
+[FEN "4k3/8/4K3/8/8/8/8/7R w - - 0 1"]
+
+1. Rh8# 1-0
+
+[Event "Unfinished"]
+
+1. d4 d5 *
+"#;
+    let games = parse_pgn(pgn).expect("Valid PGN");
+    assert_eq!(games.len(), 3);
+
+    assert_eq!(games[0].tag("Event"), Some("Casual \"blitz\""));
+    assert_eq!(games[0].result, "1-0");
+    assert_eq!(games[0].game.history().len(), 17);
+    assert_eq!(
+        games[0].game.to_fen(),
+        "r1bq1rk1/2p1bppp/p1np1n2/1p2p3/4P3/1BP2N1P/PP1P1PP1/RNBQR1K1 b - - 0 9"
+    );
+
+    assert_eq!(games[1].tag("FEN"), Some("4k3/8/4K3/8/8/8/8/7R w - - 0 1"));
+    assert_eq!(
+        games[1].game.outcome.map(|outcome| outcome.reason),
+        Some(EndReason::Checkmate)
+    );
+
+    assert_eq!(games[2].result, "*");
+    assert_eq!(sans(&games[2].game), vec!["d4", "d5"]);
+
+    // Opera game, Paris 1858
+    let game = Game::from_pgn(
+        "1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7 \
+         8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 \
+         14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0",
+    )
+    .expect("Valid PGN");
+    assert_eq!(game.last_move(), Some("Rd8#"));
+    assert_eq!(
+        game.outcome.map(|outcome| outcome.result),
+        Some(GameResult::WhiteWins)
+    );
+
+    // Exported games can be imported back
+    let mut game = standard_game();
+    play(
+        &mut game,
+        &["e4", "d5", "exd5", "Qxd5", "Nc3", "Qa5", "d4", "c6"],
+    );
+    let imported = Game::from_pgn(&game.to_pgn(&PgnTags::default())).expect("Valid PGN");
+    assert_eq!(imported.to_fen(), game.to_fen());
+    assert_eq!(sans(&imported), sans(&game));
+    assert_eq!(games[0].game.pgn_result(), "1-0");
+    assert_eq!(games[2].game.outcome, None);
+
+    // The result of a resigned game ends it, and is exported back
+    let pgn = "[Result \"0-1\"]\n\n1. e4 e5 2. Qh5 Nc6 0-1\n";
+    let game = Game::from_pgn(pgn).expect("Valid PGN");
+    assert_eq!(game.state, GameState::Ended);
+    assert_eq!(
+        game.outcome,
+        Some(Outcome {
+            result: GameResult::BlackWins,
+            reason: EndReason::Recorded,
+        })
+    );
+    let exported = game.to_pgn(&PgnTags::default());
+    assert!(exported.contains("[Result \"0-1\"]\n\n1. e4 e5 2. Qh5 Nc6 0-1\n"));
+    assert_eq!(Game::from_pgn(&exported), Ok(game));
+
+    // Chess960 games castle as such, even from the standard layout
+    let pgn = "[Variant \"Chess960\"]\n\n1. Nf3 Nf6 2. g3 g6 3. Bg2 Bg7 4. O-O *\n";
+    let game = Game::from_pgn(pgn).expect("Valid PGN");
+    assert!(game.board.chess960);
+    assert_eq!(game.last_move(), Some("O-O"));
+    assert!(is_piece::<King>(&game, Position { x: 6, y: 0 }));
+    assert!(game
+        .to_pgn(&PgnTags::default())
+        .contains("[Variant \"Chess960\"]\n"));
+    assert!(!standard_game().board.chess960);
+
+    // The FEN is ignored without a set-up position
+    let pgn = "[SetUp \"0\"]\n[FEN \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\"]\n\n1. e4 *\n";
+    let game = Game::from_pgn(pgn).expect("Valid PGN");
+    assert_eq!(
+        game.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+}
+
+#[test]
+fn pgn_import_errors() {
+    let pgn = "[Event \"First\"]\n\n1. e4 e5 1-0\n\n[Event \"Second\"]\n\n1. e4 e5 2. Ke3 *\n";
+    assert_eq!(
+        parse_pgn(pgn).err(),
+        Some(PgnError::InvalidMovement {
+            game: 2,
+            ply: 3,
+            movement: "Ke3".to_string(),
+            error: MovementError::IllegalMovement,
+        })
+    );
+    assert_eq!(
+        parse_pgn("1. e4 e5 2. Nf3 Xx9 *").err(),
+        Some(PgnError::InvalidMovement {
+            game: 1,
+            ply: 4,
+            movement: "Xx9".to_string(),
+            error: MovementError::WrongCommand("Xx9".to_string()),
+        })
+    );
+    assert_eq!(
+        parse_pgn("[Event \"Broken\"\n1. e4 *").err(),
+        Some(PgnError::InvalidTag {
+            game: 1,
+            tag: "Event \"Broken\"\n1. e4 *".to_string()
+        })
+    );
+    assert_eq!(
+        parse_pgn("1. e4 {unclosed e5 *").err(),
+        Some(PgnError::UnclosedComment { game: 1 })
+    );
+    assert_eq!(
+        parse_pgn("1. e4 e5 *\n1. e4 (1. d4 *").err(),
+        Some(PgnError::UnbalancedVariation { game: 2 })
+    );
+    assert_eq!(
+        parse_pgn("[FEN \"8/8/8/8/8/8/8/7x w - -\"]\n*").err(),
+        Some(PgnError::InvalidFen {
+            game: 1,
+            error: FenError::InvalidPlacement("8/8/8/8/8/8/8/7x".to_string()),
+        })
+    );
+    assert_eq!(Game::from_pgn("").err(), Some(PgnError::NoGame));
+}
+
+#[test]
+fn undo_and_redo() {
+    let mut game = standard_game();
+    assert_eq!(game.undo(), None);
+    assert_eq!(game.redo(), None);
+
+    // Castling rights, en passant, captures and promotions are restored
+    let fens: Vec<String> = [
+        "e4", "d5", "exd5", "c5", "dxc6", "Nf6", "cxb7", "Bf5", "bxa8=Q", "e6", "Qxb8", "Qxb8",
+    ]
+    .iter()
+    .map(|movement| {
+        let fen = game.to_fen();
+        play(&mut game, &[movement]);
+        fen
+    })
+    .collect();
+    let final_fen = game.to_fen();
+    assert_eq!(game.board.dead_pieces(Black).len(), 5);
+    assert_eq!(game.board.dead_pieces(White).len(), 1);
+
+    for fen in fens.iter().rev() {
+        assert!(game.undo().is_some());
+        assert_eq!(&game.to_fen(), fen);
+    }
+    assert_eq!(game.undo(), None);
+    assert_eq!(game.turn, 0);
+    assert!(game.board.dead_pieces(Black).is_empty());
+    assert!(game.board.dead_pieces(White).is_empty());
+    assert!(game.history().is_empty());
+
+    for fen in fens.iter().skip(1) {
+        assert!(game.redo().is_some());
+        assert_eq!(&game.to_fen(), fen);
+    }
+    assert!(game.redo().is_some());
+    assert_eq!(game.to_fen(), final_fen);
+    assert_eq!(game.redo(), None);
+    assert_eq!(game.history().len(), 12);
+    assert_eq!(game.history()[8].san, "bxa8=Q");
+
+    // Playing a new movement discards the undone ones
+    game.undo();
+    game.undo();
+    play(&mut game, &["Nf3"]);
+    assert_eq!(game.redo(), None);
+
+    // The outcome is undone as well
+    let mut game = standard_game();
+    play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+    assert_eq!(game.state, GameState::Ended);
+    assert_eq!(
+        game.undo().map(|movement| movement.to),
+        Some(Position { x: 7, y: 3 })
+    );
+    assert_eq!(game.state, GameState::OnGoing);
+    assert_eq!(game.outcome, None);
+    play(&mut game, &["Qe7"]);
+    assert_eq!(game.state, GameState::OnGoing);
+}
+
+#[test]
+fn board_is_clone_hash_and_serializable() {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+    let hash = |board: &Board| {
+        let mut hasher = DefaultHasher::new();
+        board.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let mut game = standard_game();
+    play(&mut game, &["e4", "d5", "exd5"]);
+    let board = game.board.clone();
+    assert_eq!(board, game.board);
+    assert_eq!(hash(&board), hash(&game.board));
+
+    // A clone is independent from the original
+    play(&mut game, &["Qxd5"]);
+    assert_ne!(board, game.board);
+    assert_ne!(hash(&board), hash(&game.board));
+    assert_eq!(board.dead_pieces(Black).len(), 1);
+    assert_eq!(game.board.dead_pieces(White).len(), 1);
+
+    let json = serde_json::to_string(&game.board).expect("Board is serializable");
+    let deserialized: Board = serde_json::from_str(&json).expect("Board is deserializable");
+    assert_eq!(deserialized, game.board);
+    assert!(deserialized.dead_pieces(White)[0]
+        .as_any()
+        .downcast_ref::<Pawn>()
+        .is_some());
+}
+
+#[test]
+fn board_deserialization_rebuilds_derived_fields() {
+    let game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").expect("Valid FEN");
+    let json = serde_json::to_string(&game.board).expect("Board is serializable");
+    for field in ["pieces_set", "bitboards", "pieces_key"] {
+        assert!(!json.contains(field));
+    }
+
+    // Hand-written, with stale derived fields that must be ignored
+    let json = r#"{
+        "dimension": {"x": 7, "y": 7},
+        "positions": [
+            [{"x": 4, "y": 0}, {"kind": "King", "color": "White"}],
+            [{"x": 0, "y": 0}, {"kind": "Rook", "color": "White"}],
+            [{"x": 4, "y": 7}, {"kind": "King", "color": "Black"}]
+        ],
+        "pieces_set": {"White": [], "Black": [{"x": 0, "y": 7}]},
+        "pieces_key": 12345,
+        "pieces_dead": {"White": [], "Black": []},
+        "castling_rights": {
+            "White": {"king_side": false, "queen_side": true},
+            "Black": {"king_side": false, "queen_side": false}
+        },
+        "en_passant": null
+    }"#;
+    let mut board: Board = serde_json::from_str(json).expect("Board is deserializable");
+    assert_eq!(board, game.board);
+    assert_eq!(board.zobrist(), game.board.zobrist());
+    let mut expected = game.board.clone();
+    assert_eq!(
+        board.legal_moves(White).into_iter().collect::<HashSet<_>>(),
+        expected
+            .legal_moves(White)
+            .into_iter()
+            .collect::<HashSet<_>>()
+    );
+
+    // Pieces out of the `Board`, or in the same square, are rejected
+    let out = json.replace(r#"{"x": 0, "y": 0}"#, r#"{"x": 8, "y": 0}"#);
+    assert!(serde_json::from_str::<Board>(&out).is_err());
+    let twice = json.replace(r#"{"x": 0, "y": 0}"#, r#"{"x": 4, "y": 0}"#);
+    assert!(serde_json::from_str::<Board>(&twice).is_err());
+}
+
+#[test]
+fn bitboards_match_square_by_square_backend() {
+    let fens = [
+        STARTING_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ];
+    let moves = |board: &mut Board, color: Color| -> HashSet<Movement> {
+        board.legal_moves(color).into_iter().collect()
+    };
+
+    for fen in fens {
+        let game = Game::from_fen(fen).expect("Valid FEN");
+        let color = Game::static_playing_color(game.turn);
+        let mut bitboards = game.board.clone();
+        let mut squares = game.board.clone();
+        squares.use_bitboards(false);
+        assert_ne!(bitboards, squares);
+
+        let movements = moves(&mut bitboards, color);
+        assert_eq!(movements, moves(&mut squares, color));
+
+        // Compare the replies after every movement as well
+        for movement in movements {
+            let mut bitboards = bitboards.clone();
+            let mut squares = squares.clone();
+            assert_ok!(bitboards.move_piece(color, &movement));
+            assert_ok!(squares.move_piece(color, &movement));
+            assert_eq!(bitboards.is_in_check(!color), squares.is_in_check(!color));
+            assert_eq!(moves(&mut bitboards, !color), moves(&mut squares, !color));
+        }
+    }
+}
+
+#[test]
+fn perft_reference_positions() {
+    // Node counts from https://www.chessprogramming.org/Perft_Results
+    let positions: [(&str, &[u64]); 6] = [
+        (STARTING_FEN, &[20, 400, 8902]),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039, 97862],
+        ),
+        (
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238],
+        ),
+        (
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467],
+        ),
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379],
+        ),
+        (
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890],
+        ),
+    ];
+
+    for (fen, nodes) in positions {
+        let mut game = Game::from_fen(fen).expect("Valid FEN");
+        for (depth, expected) in nodes.iter().enumerate() {
+            assert_eq!(
+                game.perft(depth as u32 + 1),
+                *expected,
+                "{} depth {}",
+                fen,
+                depth + 1
+            );
+        }
+        // Perft leaves the position untouched
+        assert_eq!(game.to_fen(), fen);
+    }
+}
+
+#[test]
+fn perft_divide() {
+    let mut game = standard_game();
+    assert_eq!(game.perft(0), 1);
+
+    let divide = game.divide(3);
+    assert_eq!(divide.len(), 20);
+    assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 8902);
+    let nodes = |name: &str| {
+        divide
+            .iter()
+            .find(|(movement, _)| movement.to_string() == name)
+            .map(|(_, nodes)| *nodes)
+    };
+    assert_eq!(nodes("e2e4"), Some(600));
+    assert_eq!(nodes("g1f3"), Some(440));
+    assert_eq!(nodes("a2a3"), Some(380));
+    assert_eq!(nodes("e2e5"), None);
+}
+
+#[test]
+fn zobrist_hash_matches_polyglot_keys() {
+    // Reference keys from the Polyglot book format specification
+    let lines: [&[(&str, Option<u64>)]; 2] = [
+        &[
+            ("e4", Some(0x823c9b50fd114196)),
+            ("d5", Some(0x0756b94461c50fb0)),
+            ("e5", Some(0x662fafb965db29d4)),
+            ("f5", Some(0x22a48b5a8e47ff78)),
+            ("Ke2", Some(0x652a607ca3f242c1)),
+            ("Kf7", Some(0x00fdd303c946bdd9)),
+        ],
+        &[
+            ("a4", None),
+            ("b5", None),
+            ("h4", None),
+            ("b4", None),
+            ("c4", Some(0x3c8123ea7b067637)),
+            ("bxc3", None),
+            ("Ra3", Some(0x5c3f9b829b279560)),
+        ],
+    ];
+
+    for line in lines {
+        let mut game = standard_game();
+        assert_eq!(game.hash(), 0x463b96181691fc9c);
+        for (movement, key) in line {
+            play(&mut game, &[movement]);
+            if let Some(key) = key {
+                assert_eq!(game.hash(), *key, "after {}", movement);
+            }
+            // The same position set up from its FEN has the same hash
+            let fen = game.to_fen();
+            assert_eq!(Game::from_fen(&fen).expect("Valid FEN").hash(), game.hash());
+        }
+
+        // Undoing every movement restores the initial hash
+        while game.undo().is_some() {}
+        assert_eq!(game.hash(), 0x463b96181691fc9c);
+    }
+}
+
+#[test]
+fn zobrist_hash_tracks_lost_castling_rights() {
+    let mut game = standard_game();
+    play(&mut game, &["e4", "e5"]);
+    let with_rights = game.hash();
+
+    // The kings come back, but their castling rights do not
+    let shuffle = ["Ke2", "Ke7", "Ke1", "Ke8"];
+    play(&mut game, &shuffle);
+    assert_eq!(
+        game.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w - - 4 4"
+    );
+    assert_ne!(game.hash(), with_rights);
+    let fen = game.to_fen();
+    assert_eq!(Game::from_fen(&fen).expect("Valid FEN").hash(), game.hash());
+
+    play(&mut game, &shuffle);
+    assert_eq!(game.claimable_draw(), None);
+    play(&mut game, &shuffle);
+    assert_eq!(game.claimable_draw(), Some(EndReason::ThreefoldRepetition));
+}
+
+#[test]
+fn game_serde_round_trip() {
+    let round_trip = |game: &Game| -> Game {
+        let json = serde_json::to_string(game).expect("Game is serializable");
+        serde_json::from_str(&json).expect("Game is deserializable")
+    };
+
+    let mut game = standard_game();
+    play(&mut game, &["e4", "d5", "exd5", "Qxd5", "Nc3"]);
+    game.undo();
+
+    let mut restored = round_trip(&game);
+    assert_eq!(restored, game);
+    assert_eq!(restored.hash(), game.hash());
+    assert_eq!(restored.to_fen(), game.to_fen());
+    assert_eq!(sans(&restored), ["e4", "d5", "exd5", "Qxd5"]);
+    assert!(restored.board.dead_pieces(White)[0]
+        .as_any()
+        .downcast_ref::<Pawn>()
+        .is_some());
+
+    // History and redo stack keep working after restoring
+    assert_eq!(
+        restored.redo().map(|movement| movement.to_string()),
+        Some("b1c3".to_string())
+    );
+    assert_eq!(
+        restored.undo().map(|movement| movement.to_string()),
+        Some("b1c3".to_string())
+    );
+    assert_eq!(
+        restored.undo().map(|movement| movement.to_string()),
+        Some("d8d5".to_string())
+    );
+    assert_eq!(restored.board.dead_pieces(White).len(), 0);
+
+    // A finished game keeps its outcome
+    let mut game = standard_game();
+    play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+    let restored = round_trip(&game);
+    assert_eq!(restored.state, GameState::Ended);
+    assert_eq!(restored.outcome, game.outcome);
+    assert_eq!(restored.pgn_result(), "0-1");
+}
+
+#[test]
+fn engine_finds_mates_and_material() {
+    let depth = |depth| SearchLimits {
+        depth: Some(depth),
+        ..Default::default()
+    };
+    let best_move = |fen: &str, limits| {
+        let game = Game::from_fen(fen).expect("Valid FEN");
+        let result = game.best_move(limits);
+        let movement = result.best_move.map(|movement| movement.to_string());
+        (movement, result)
+    };
+
+    // Back rank mate in one
+    let (movement, result) = best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", depth(3));
+    assert_eq!(movement.as_deref(), Some("a1a8"));
+    assert_eq!(result.mate_in(), Some(1));
+    assert_eq!(result.score, MATE_SCORE - 1);
+
+    // Rook ladder mate in two
+    let (_, result) = best_move("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", depth(4));
+    assert_eq!(result.mate_in(), Some(2));
+    assert_eq!(result.pv.len(), 3);
+
+    // Capture a hanging queen, for both colors
+    let (movement, result) = best_move("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", depth(2));
+    assert_eq!(movement.as_deref(), Some("d2d5"));
+    assert!(result.score > 400);
+    let (movement, result) = best_move("4k3/3r4/8/8/3Q4/8/8/4K3 b - - 0 1", depth(2));
+    assert_eq!(movement.as_deref(), Some("d7d4"));
+    assert!(result.score > 400);
+
+    // Quiescence sees the recapture of a defended knight
+    let (movement, _) = best_move("4k3/8/2p5/3n4/8/8/3R4/4K3 w - - 0 1", depth(1));
+    assert_ne!(movement.as_deref(), Some("d2d5"));
+
+    // No legal movements
+    let (movement, result) = best_move("7k/5QQ1/8/8/8/8/8/K7 b - - 0 1", depth(3));
+    assert_eq!(movement, None);
+    assert_eq!(result.score, -MATE_SCORE);
+    let (movement, result) = best_move("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", depth(3));
+    assert_eq!(movement, None);
+    assert_eq!(result.score, 0);
+}
+
+#[test]
+fn engine_principal_variation_and_time_budget() {
+    let game = standard_game();
+    let mut board = game.board.clone();
+    assert_eq!(evaluate(&board, White), 0);
+
+    let mut engine = Engine::default();
+    let mut iterations = Vec::new();
+    let result = engine.search_with(
+        &game,
+        SearchLimits {
+            depth: Some(3),
+            ..Default::default()
+        },
+        |iteration| iterations.push(iteration.depth),
+    );
+    assert_eq!(iterations, [1, 2, 3]);
+    assert_eq!(result.depth, 3);
+    assert_eq!(result.best_move, result.pv.first().copied());
+    assert!(result.nodes > 0);
+
+    // The principal variation is a sequence of legal movements
+    let mut color = White;
+    for movement in result.pv.iter() {
+        assert_ok!(board.move_piece(color, movement));
+        color = !color;
+    }
+
+    // A time budget still completes the first iteration
+    let result = Engine::default().search(
+        &game,
+        SearchLimits {
+            time: Some(std::time::Duration::from_millis(1)),
+            ..Default::default()
+        },
+    );
+    assert!(result.depth >= 1);
+    assert!(result.best_move.is_some());
+
+    // Clock time is split among the remaining movements, keeping a margin
+    let seconds = std::time::Duration::from_secs;
+    let limits = SearchLimits::from_clock(seconds(60), seconds(0), Some(20));
+    assert_eq!(limits.time, Some(seconds(3)));
+    let limits = SearchLimits::from_clock(seconds(1), seconds(4), None);
+    assert!(limits.time < Some(seconds(1) / 2));
+}
+
+/// Returns a Polyglot entry for a position key, movement (`from`, `to` and promotion) and weight
+fn book_entry(key: u64, from: &str, to: &str, promotion: u16, weight: u16) -> Vec<u8> {
+    let square = |name: &str| {
+        let position: Position = name.parse().expect("Valid square");
+        (position.y * 8 + position.x) as u16
+    };
+    let movement = promotion << 12 | square(from) << 6 | square(to);
+    let mut entry = key.to_be_bytes().to_vec();
+    entry.extend(movement.to_be_bytes());
+    entry.extend(weight.to_be_bytes());
+    entry.extend(0u32.to_be_bytes());
+    entry
+}
+
+#[test]
+fn polyglot_book() {
+    let start = 0x463b96181691fc9c;
+    let castling_fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let castling_key = Game::from_fen(castling_fen).expect("Valid FEN").hash();
+    let promotion_fen = "8/1P4k1/8/8/8/8/8/4K3 w - - 0 1";
+    let promotion_key = Game::from_fen(promotion_fen).expect("Valid FEN").hash();
+    let bytes: Vec<u8> = [
+        book_entry(castling_key, "e1", "h1", 0, 1),
+        book_entry(castling_key, "e1", "a1", 0, 1),
+        book_entry(start, "e2", "e4", 0, 30),
+        book_entry(start, "d2", "d4", 0, 20),
+        book_entry(start, "g1", "f3", 0, 10),
+        // Not legal in the position, so it is skipped
+        book_entry(start, "e2", "e5", 0, 100),
+        book_entry(promotion_key, "b7", "b8", 1, 1),
+    ]
+    .concat();
+    let book = PolyglotBook::from_bytes(&bytes).expect("Valid book");
+    assert_eq!(book.len(), 7);
+
+    let names = |game: &Game| -> Vec<String> {
+        book.moves(game)
+            .iter()
+            .map(|book_move| book_move.movement.to_string())
+            .collect()
+    };
+    let mut game = standard_game();
+    assert_eq!(names(&game), ["e2e4", "d2d4", "g1f3"]);
+    assert_eq!(
+        book.select(&game, BookSelection::Best)
+            .map(|movement| movement.to_string()),
+        Some("e2e4".to_string())
+    );
+
+    // Weighted selection is reproducible and plays every movement sometimes
+    let picks: HashSet<String> = (0..100)
+        .filter_map(|seed| book.select(&game, BookSelection::Weighted(seed)))
+        .map(|movement| movement.to_string())
+        .collect();
+    assert_eq!(picks.len(), 3);
+    assert_eq!(
+        book.select(&game, BookSelection::Weighted(7)),
+        book.select(&game, BookSelection::Weighted(7))
+    );
+
+    // Castling is encoded as the `King` capturing its own `Rook`
+    let castling = Game::from_fen(castling_fen).expect("Valid FEN");
+    assert_eq!(names(&castling), ["e1g1", "e1c1"]);
+    let promotion = Game::from_fen(promotion_fen).expect("Valid FEN");
+    assert_eq!(names(&promotion), ["b7b8n"]);
+
+    // Out of the book
+    play(&mut game, &["a3"]);
+    assert!(book.moves(&game).is_empty());
+    assert_eq!(book.select(&game, BookSelection::Best), None);
+
+    assert_eq!(
+        PolyglotBook::from_bytes(&bytes[..20]),
+        Err(BookError::InvalidSize(20))
+    );
+    assert!(matches!(
+        PolyglotBook::open("missing-book.bin"),
+        Err(BookError::Io(_))
+    ));
+}
+
+#[cfg(feature = "syzygy")]
+#[test]
+fn syzygy_tablebase_unavailable() {
+    let dir = std::env::temp_dir().join(format!("rust-chess-syzygy-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("Temporary directory");
+    // Right size but no valid content, so the table is listed but can not be probed
+    for table in ["KQvK.rtbw", "KQvK.rtbz", "KRvK.rtbw", "KRvK.rtbz"] {
+        fs::write(dir.join(table), [0u8; 16]).expect("Table written");
+    }
+    fs::write(dir.join("README.txt"), "Not a table").expect("File written");
+
+    let tablebase = Tablebase::open(&dir).expect("Tables are listed");
+    assert_eq!(tablebase.max_pieces(), 3);
+
+    assert_eq!(
+        tablebase.probe(&standard_game()),
+        Err(TablebaseError::TooManyPieces(32))
+    );
+    let castling = Game::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").expect("Valid FEN");
+    assert_eq!(tablebase.probe(&castling), Err(TablebaseError::Castling));
+    let mut corrupted = Game::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").expect("Valid FEN");
+    corrupted.start();
+    assert!(matches!(
+        tablebase.probe(&corrupted),
+        Err(TablebaseError::Probe(_))
+    ));
+    // Nothing is adjudicated without a known result
+    assert_eq!(corrupted.adjudicate(&tablebase), None);
+    assert_eq!(corrupted.state, GameState::OnGoing);
+
+    let mut small = Game::new(Board::new(Position { x: 5, y: 5 }, None));
+    small.set_layout(&Layout::los_alamos());
+    assert_eq!(
+        tablebase.probe(&small),
+        Err(TablebaseError::UnsupportedBoard)
+    );
+
+    fs::remove_dir_all(&dir).expect("Temporary directory removed");
+    assert!(matches!(Tablebase::open(&dir), Err(TablebaseError::Io(_))));
+}
+
+#[cfg(feature = "syzygy")]
+#[test]
+fn syzygy_tablebase_adjudication() {
+    // Only the KQvK WDL table, without DTZ tables. It was built for these tests and checked
+    // against every KQvK position: White to move wins, Black to move loses unless stalemated
+    // or able to capture the `Queen`
+    let tablebase = Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy"))
+        .expect("Tables are listed");
+    assert_eq!(tablebase.max_pieces(), 3);
+    let probe = |fen| tablebase.probe(&Game::from_fen(fen).expect("Valid FEN"));
+
+    assert_eq!(
+        probe("4k3/8/8/8/8/8/8/3QK3 w - - 0 1"),
+        Ok(Probe {
+            wdl: Wdl::Win,
+            dtz: None,
+        })
+    );
+    assert_eq!(
+        probe("k7/8/8/8/8/8/8/1Q2K3 b - - 0 1").map(|probe| probe.wdl),
+        Ok(Wdl::Loss)
+    );
+    // Stalemate, and a capture of the `Queen` leaving bare kings
+    assert_eq!(
+        probe("k7/8/1QK5/8/8/8/8/8 b - - 0 1").map(|probe| probe.wdl),
+        Ok(Wdl::Draw)
+    );
+    assert_eq!(
+        probe("8/8/8/8/8/8/1Q6/k3K3 b - - 0 1"),
+        Ok(Probe {
+            wdl: Wdl::Draw,
+            dtz: Some(0),
+        })
+    );
+    // The table covers the colors swapped
+    assert_eq!(
+        probe("3qk3/8/8/8/8/8/8/4K3 w - - 0 1").map(|probe| probe.wdl),
+        Ok(Wdl::Loss)
+    );
+
+    let mut game = Game::from_fen("3qk3/8/8/8/8/8/8/4K3 w - - 0 1").expect("Valid FEN");
+    game.start();
+    let outcome = Outcome {
+        result: GameResult::BlackWins,
+        reason: EndReason::Tablebase,
+    };
+    assert_eq!(game.adjudicate(&tablebase), Some(outcome));
+    assert_eq!(game.state, GameState::Ended);
+    assert_eq!(game.outcome, Some(outcome));
+    assert_eq!(game.adjudicate(&tablebase), None);
+
+    let mut game = standard_game();
+    assert_eq!(game.adjudicate(&tablebase), None);
+    assert_eq!(game.state, GameState::OnGoing);
+}
+
+#[test]
+fn chess960_layouts_and_castling() {
+    let layouts = [
+        (
+            0,
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1",
+        ),
+        (518, STARTING_FEN),
+        (
+            959,
+            "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1",
+        ),
+    ];
+    for (index, fen) in layouts {
+        let mut game = Game::new(create_board());
+        assert_eq!(game.set_board_960(Some(index)), Ok(index));
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.perft(2), 400);
+    }
+    let mut game = Game::new(create_board());
+    assert!(game.set_board_960(None).is_ok_and(|index| index < 960));
+    assert!(game
+        .to_pgn(&PgnTags::default())
+        .contains("[Variant \"Chess960\"]"));
+
+    // Scharnagl numbers out of range are rejected, not wrapped
+    let mut game = standard_game();
+    assert_eq!(
+        game.set_board_960(Some(960)),
+        Err(LayoutError::InvalidChess960(960))
+    );
+    assert_eq!(game.to_fen(), STARTING_FEN);
+    assert_eq!(
+        Layout::chess960(1478),
+        Err(LayoutError::InvalidChess960(1478))
+    );
+
+    // Reference counts of Chess960 positions, with castling rights in Shredder-FEN
+    let positions = [
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            12189,
+        ),
+        (
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+            13440,
+        ),
+    ];
+    for (fen, nodes) in positions {
+        let mut game = Game::from_fen(fen).expect("Valid FEN");
+        assert_eq!(game.to_shredder_fen(), fen);
+        assert_eq!(game.perft(3), nodes);
+    }
+
+    // `King` and `Rook` land in the standard squares, even the other's initial square
+    let fen = "4k3/8/8/8/8/8/8/RK3R2 w FA - 0 1";
+    let mut game = Game::from_fen(fen).expect("Valid FEN");
+    game.start();
+    assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/RK3R2 w KQ - 0 1");
+    play(&mut game, &["b1f1"]);
+    assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/R4RK1 b - - 1 1");
+    assert_eq!(game.history()[0].san, "O-O");
+    game.undo();
+    assert_eq!(game.to_shredder_fen(), fen);
+    play(&mut game, &["O-O-O"]);
+    assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/2KR1R2 b - - 1 1");
+
+    // The `King` can not cross attacked squares, but the `Rook` can. Neither can cross pieces
+    let mut game = Game::from_fen("2r1k3/8/8/8/8/8/8/RK3R2 w FA - 0 1").expect("Valid FEN");
+    game.start();
+    assert_err!(game.move_piece("b1a1".to_string()), MovementError::Check);
+    assert_err!(game.move_piece("b1f1".to_string()), MovementError::Check);
+    let mut game = Game::from_fen("3rk3/8/8/8/8/8/8/RK2NR2 w FA - 0 1").expect("Valid FEN");
+    game.start();
+    assert_err!(
+        game.move_piece("b1f1".to_string()),
+        MovementError::BlockedPath
+    );
+    assert_ok!(game.move_piece("b1a1".to_string()));
+
+    // X-FEN names the `Rook` file when it is not the outermost one
+    let fen = "4k3/8/8/8/8/8/8/R1R1K3 w C - 0 1";
+    let game = Game::from_fen(fen).expect("Valid FEN");
+    assert_eq!(game.to_fen(), fen);
+    assert_eq!(
+        Game::from_fen("4k3/8/8/8/8/8/8/R1R1K3 w Q - 0 1")
+            .expect("Valid FEN")
+            .to_shredder_fen(),
+        "4k3/8/8/8/8/8/8/R1R1K3 w A - 0 1"
+    );
+    assert_eq!(
+        Game::from_fen(STARTING_FEN)
+            .expect("Valid FEN")
+            .to_shredder_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+    );
+    assert_eq!(
+        Game::from_fen("4k3/8/8/8/8/8/8/R3K2R w KH - 0 1").map(|_| ()),
+        Err(FenError::InvalidCastling("KH".to_string()))
+    );
+}
+
+#[test]
+fn board_layouts() {
+    let mut game = Game::new(create_board());
+    game.set_layout(&Layout::capablanca());
+    assert_eq!(game.board.dimension, Position { x: 9, y: 7 });
+    assert_eq!(
+        game.to_fen(),
+        "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1"
+    );
+    assert_eq!(game.perft(1), 28);
+    assert_eq!(game.perft(2), 784);
+
+    let mut game = Game::new(create_board());
+    game.set_layout(&Layout::gardner());
+    assert_eq!(game.to_fen(), "rnbqk/ppppp/5/PPPPP/RNBQK w - - 0 1");
+    assert_eq!(game.perft(1), 7);
+    assert_eq!(game.perft(2), 53);
+
+    let mut game = Game::new(create_board());
+    game.set_layout(&Layout::los_alamos());
+    assert_eq!(game.to_fen(), "rnqknr/pppppp/6/6/PPPPPP/RNQKNR w - - 0 1");
+    // Pawns do not move two squares
+    assert_eq!(game.perft(1), 10);
+    game.start();
+    assert!(game.move_piece("a2a4".to_string()).is_err());
+    play(&mut game, &["a2a3", "b5b4"]);
+    let board: Board =
+        serde_json::from_str(&serde_json::to_string(&game.board).expect("Board is serializable"))
+            .expect("Board is deserializable");
+    assert_eq!(board, game.board);
+
+    let mut game = Game::new(create_board());
+    game.set_layout(&Layout::standard());
+    assert_eq!(game.to_fen(), STARTING_FEN);
+
+    assert_eq!(
+        Layout::new("RNBQKBNR", 8, true, PawnRules::default()),
+        Ok(Layout::standard())
+    );
+    assert_eq!(Layout::chess960(518), Ok(Layout::standard()));
+    assert_eq!(
+        Layout::new("RNBPKBNR", 8, true, PawnRules::default()),
+        Err(LayoutError::InvalidPiece('P'))
+    );
+    assert_eq!(
+        Layout::new("RNBQXBNR", 8, true, PawnRules::default()),
+        Err(LayoutError::InvalidPiece('X'))
+    );
+    assert_eq!(
+        Layout::new("RNBQQBNR", 8, true, PawnRules::default()),
+        Err(LayoutError::KingsNumber(0))
+    );
+    assert_eq!(
+        Layout::new("RNK", 3, false, PawnRules::default()),
+        Err(LayoutError::TooFewRanks(3))
+    );
+    // Files are named from `a` to `z`
+    let back_rank = format!("{}K", "N".repeat(25));
+    assert_ok!(Layout::new(&back_rank, 8, false, PawnRules::default()));
+    assert_eq!(
+        Layout::new(&format!("N{}", back_rank), 8, false, PawnRules::default()),
+        Err(LayoutError::TooManyFiles(27))
+    );
+    assert_ok!(Layout::new("RNBQKBNR", 99, true, PawnRules::default()));
+    assert_eq!(
+        Layout::new("RNBQKBNR", 100, true, PawnRules::default()),
+        Err(LayoutError::TooManyRanks(100))
+    );
+}
+
+#[test]
+fn los_alamos_promotions() {
+    let dimension = Position { x: 5, y: 5 };
+    let mut board = Board::new(
+        dimension,
+        Some(vec![
+            (Position { x: 0, y: 4 }, PieceFactory::create::<Pawn>(White)),
+            (Position { x: 3, y: 0 }, PieceFactory::create::<King>(White)),
+            (Position { x: 3, y: 5 }, PieceFactory::create::<King>(Black)),
+        ]),
+    );
+    board.pawn_rules = PawnRules {
+        double_step: false,
+        bishop_promotion: false,
+    };
+    let promotions: HashSet<Option<Promotion>> = board
+        .legal_moves(White)
+        .into_iter()
+        .filter(|movement| movement.from == Position { x: 0, y: 4 })
+        .map(|movement| movement.promotion)
+        .collect();
+    assert_eq!(
+        promotions,
+        HashSet::from([
+            Some(Promotion::Queen),
+            Some(Promotion::Rook),
+            Some(Promotion::Knight),
+        ])
+    );
+
+    let mut game = Game::new(board);
+    game.start();
+    assert!(game.move_piece("a5a6b".to_string()).is_err());
+    assert!(game.move_piece("a6=B".to_string()).is_err());
+    play(&mut game, &["a5a6n"]);
+    assert_eq!(
+        game.board.positions.get(&Position { x: 0, y: 5 }),
+        Some(&ChessPiece::new(PieceKind::Knight, White))
+    );
+}
+
+#[test]
+fn capablanca_pieces_and_castling() {
+    let mut game = Game::new(create_board());
+    game.set_layout(&Layout::capablanca());
+    game.start();
+    // The `Archbishop` and the `Chancellor` jump like a `Knight` too
+    assert!(game.move_piece("c1c3".to_string()).is_err());
+    play(&mut game, &["c1d3", "h8i6", "d3f5"]);
+    assert!(game.move_piece("i6g4".to_string()).is_err());
+    play(&mut game, &["i6i3"]);
+
+    // The `King` castles three squares sideways to the `i` file, not capturing its own `Rook`
+    let mut game = Game::new(create_board());
+    game.set_layout(&Layout::capablanca());
+    game.start();
+    assert!(!game.board.chess960);
+    play(
+        &mut game,
+        &[
+            "i1j3", "a7a6", "h1i3", "a6a5", "h2h3", "a5a4", "g1h2", "b7b6",
+        ],
+    );
+    assert!(game.move_piece("f1j1".to_string()).is_err());
+    play(&mut game, &["f1i1"]);
+    assert_eq!(game.last_move(), Some("O-O"));
+    assert_eq!(
+        game.to_fen(),
+        "rnabqkbcnr/2pppppppp/1p8/10/p9/7PCN/PPPPPPPBPP/RNABQ2RK1 b kq - 1 5"
+    );
+    let fen = game.to_fen();
+    assert_eq!(Game::from_fen(&fen).expect("Valid FEN").board, game.board);
+}
+
+#[test]
+fn custom_pieces() {
+    #[derive(Debug, Piece)]
+    #[piece(kind = Knight)]
+    struct Horse(Color);
+
+    #[derive(Debug, Piece)]
+    #[piece(symbol = 'W', moves(king, knight))]
+    struct Wizard(Color);
+
+    let wizard = PieceKind::Custom {
+        symbol: 'W',
+        moves: Moves {
+            king: true,
+            knight: true,
+            ..Moves::default()
+        },
+    };
+    assert_eq!(Horse::new(White).kind(), PieceKind::Knight);
+    assert_eq!(Wizard::new(Black).kind(), wizard);
+    assert_eq!(Wizard::new(Black).symbol(), 'w');
+
+    let d4 = Position { x: 3, y: 3 };
+    let e6 = Position { x: 4, y: 5 };
+    let mut game = Game::new(create_board());
+    assert_ok!(game.board.add_pieces(vec![
+        (Position { x: 4, y: 0 }, PieceFactory::create::<King>(White)),
+        (Position { x: 4, y: 7 }, PieceFactory::create::<King>(Black)),
+        (d4, PieceFactory::create::<Wizard>(White)),
+        (
+            Position { x: 0, y: 7 },
+            PieceFactory::create::<Horse>(Black)
+        ),
+    ]));
+    game.start();
+
+    // The `Wizard` keeps its kind in the `Board`, and steps as a `King` or jumps as a `Knight`
+    assert_eq!(game.board.legal_moves_from(d4).len(), 16);
+    assert!(game.move_piece("d4d6".to_string()).is_err());
+    play(&mut game, &["d4e6"]);
+    assert_eq!(game.last_move(), Some("We6"));
+    assert_eq!(
+        game.board.positions.get(&e6),
+        Some(&ChessPiece::new(wizard, White))
+    );
+    assert_eq!(game.to_fen(), "n3k3/8/4W3/8/8/8/8/4K3 b - - 1 1");
+
+    // And it attacks the squares it moves to
+    assert!(game.move_piece("e8d8".to_string()).is_err());
+    assert!(game.move_piece("e8e7".to_string()).is_err());
+    assert!(game.move_piece("e8f7".to_string()).is_err());
+    play(&mut game, &["a8b6"]);
+}
+
+#[test]
+fn tall_boards_and_bounds() {
+    let layout = Layout::new("RNBQKBNR", 12, true, PawnRules::default()).expect("Valid layout");
+    let mut game = Game::new(create_board());
+    game.set_layout(&layout);
+    game.start();
+    play(&mut game, &["e2e4", "e11e9", "g1f3", "b12c10"]);
+    assert_eq!(
+        game.board.positions.get(&Position { x: 2, y: 9 }),
+        Some(&ChessPiece::new(PieceKind::Knight, Black))
+    );
+    for movement in ["e10e9e8", "e10e9x", "1e10e", "e0e1"] {
+        assert!(game.move_piece(movement.to_string()).is_err());
+    }
+
+    let board = game.print_board(White);
+    let lines: Vec<&str> = board.lines().collect();
+    assert_eq!(lines.len(), 13);
+    assert!(lines[0].starts_with("12 "));
+    assert!(lines[11].starts_with(" 1 "));
+    assert_eq!(lines[12], "    a  b  c  d  e  f  g  h ");
+    let board = game.print_board(Black);
+    let lines: Vec<&str> = board.lines().collect();
+    assert!(lines[0].starts_with(" 1 "));
+    assert_eq!(lines[12], "    h  g  f  e  d  c  b  a ");
+
+    // Pieces out of the `Board` are rejected, and none is added
+    let mut board = create_board();
+    assert_err!(
+        board.add_pieces(vec![
+            (Position { x: 0, y: 0 }, PieceFactory::create::<King>(White)),
+            (Position { x: 8, y: 0 }, PieceFactory::create::<King>(Black)),
+        ]),
+        MovementError::OutOfBounds
+    );
+    assert!(board.positions.is_empty());
+
+    // So are pieces in occupied squares, or in the same square as another one
+    let a1 = Position { x: 0, y: 0 };
+    assert_err!(
+        board.add_pieces(vec![
+            (a1, PieceFactory::create::<King>(White)),
+            (a1, PieceFactory::create::<King>(Black)),
+        ]),
+        MovementError::Occupied(a1)
+    );
+    assert!(board.positions.is_empty());
+    assert_ok!(board.add_pieces(vec![(a1, PieceFactory::create::<King>(White))]));
+    assert_err!(
+        board.add_pieces(vec![
+            (Position { x: 7, y: 7 }, PieceFactory::create::<King>(Black)),
+            (a1, PieceFactory::create::<Rook>(White)),
+        ]),
+        MovementError::Occupied(a1)
+    );
+    assert_eq!(board.positions.len(), 1);
+
+    assert_eq!(
+        Board::try_new(
+            Position { x: 7, y: 7 },
+            Some(vec![
+                (a1, PieceFactory::create::<King>(White)),
+                (a1, PieceFactory::create::<Rook>(White)),
+            ])
+        ),
+        Err(MovementError::Occupied(a1))
+    );
+    assert_eq!(
+        Board::try_new(Position { x: 26, y: 7 }, None),
+        Err(MovementError::OutOfBounds)
+    );
+    assert_eq!(
+        Board::try_new(Position { x: 7, y: -1 }, None),
+        Err(MovementError::OutOfBounds)
+    );
+    assert!(Board::try_new(Position { x: 25, y: 7 }, None).is_ok());
+    assert_eq!(
+        Board::try_new(Position { x: 7, y: 99 }, None),
+        Err(MovementError::OutOfBounds)
+    );
+    assert!(Board::try_new(Position { x: 7, y: 98 }, None).is_ok());
+
+    // FENs are bounded likewise
+    let placement = |ranks: usize| format!("k7/{}K7", "8/".repeat(ranks - 2));
+    assert!(Game::from_fen(&format!("{} w - - 0 1", placement(99))).is_ok());
+    assert_eq!(
+        Game::from_fen(&format!("{} w - - 0 1", placement(100))).err(),
+        Some(FenError::InvalidPlacement(placement(100)))
+    );
+}
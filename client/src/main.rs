@@ -32,7 +32,7 @@ async fn main() -> core::result::Result<(), String> {
     let (shutdown_tx, mut shutdown_rx) = mpsc::channel(32);
     let shutdown_tx_clone = shutdown_tx.clone();
     tokio::spawn(async move {
-        if let Ok(_) = signal::ctrl_c().await {
+        if signal::ctrl_c().await.is_ok() {
             println!("\n\nPress ENTER to exit");
             let _ = shutdown_tx_clone
                 .send("Received Ctrl+C, shutting down.")
@@ -51,7 +51,6 @@ async fn main() -> core::result::Result<(), String> {
     result
         .map(|msg| {
             println!("{:?}", msg);
-            ()
         })
         .map_err(|_| "Connection closed by Server".to_string())
 }
@@ -73,6 +73,12 @@ pub async fn run(
             turn_color = Game::static_playing_color(chess_response.turn);
 
             print!("\n\n{}", chess_response.board);
+            if let Some(last_move) = &chess_response.last_move {
+                println!("\n{:?} played {}", !turn_color, last_move);
+            }
+            if chess_response.check {
+                println!("\n{:?} is in check!", turn_color);
+            }
         } else if response.is_error() {
             if let Response::Error { ref error, .. } = response {
                 if error.code == CONNECTION_CLOSED_BY_SERVER {
@@ -88,5 +94,9 @@ pub async fn run(
         }
     }
 
-    Ok("Game finished".to_string())
+    Ok(chess_response
+        .outcome
+        .map_or("Game finished".to_string(), |outcome| {
+            format!("Game finished. {}", outcome)
+        }))
 }
@@ -32,19 +32,17 @@ impl Future for ReadSocket {
 
                 match unsafe { Pin::new_unchecked(&mut read_future).poll(ctx) } {
                     // If data is ready to be read in socket I return
-                    Poll::Ready(n) => {
-                        return Poll::Ready(buf[..n.unwrap()].to_vec());
-                    }
+                    Poll::Ready(n) => Poll::Ready(buf[..n.unwrap()].to_vec()),
                     Poll::Pending => {
                         drop(socket_guard);
                         ctx.waker().wake_by_ref();
-                        return Poll::Pending;
+                        Poll::Pending
                     }
                 }
             }
             Poll::Pending => {
                 ctx.waker().wake_by_ref();
-                return Poll::Pending;
+                Poll::Pending
             }
         }
     }
@@ -155,7 +155,7 @@ pub fn rpc_method(
         .collect();
 
     let new_sig_ident = &format!("inner_{}", original_input_ident);
-    input.sig.ident = syn::Ident::new(&new_sig_ident, proc_macro2::Span::call_site());
+    input.sig.ident = syn::Ident::new(new_sig_ident, proc_macro2::Span::call_site());
     let input_sig_ident = &input.sig.ident;
     input_wrapper.sig.asyncness = None;
 
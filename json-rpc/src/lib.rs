@@ -115,8 +115,8 @@ impl Display for Response {
                 write!(f, "{}", result)
             }
             Err((err, _)) => {
-                let error_msg = format!("Error");
-                write!(f, "{}: {}\n", error_msg.red(), err.message)
+                let error_msg = "Error".to_string();
+                writeln!(f, "{}: {}", error_msg.red(), err.message)
             }
         }
     }
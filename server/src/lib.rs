@@ -1,4 +1,7 @@
-use chess_lib::{game::GameState, pieces::Color};
+use chess_lib::{
+    game::{GameState, Outcome},
+    pieces::Color,
+};
 use serde::{Deserialize, Serialize};
 
 /// Response returned by the server
@@ -9,4 +12,13 @@ pub struct ChessResponse {
     pub turn: u32,
     pub board: String,
     pub game_state: GameState,
+    /// Whether the `Color` to move is in check
+    #[serde(default)]
+    pub check: bool,
+    /// SAN of the last movement played
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_move: Option<String>,
+    /// How the game finished, once ended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<Outcome>,
 }
@@ -5,9 +5,18 @@
 //! Waits for two clients (White & Black) to connect
 use chess_lib::board::{Board, Position};
 use chess_lib::game::Game;
+use chess_lib::pgn::PgnTags;
 use chess_lib::pieces::{Color, Piece};
+#[cfg(feature = "syzygy")]
+use chess_lib::tablebase::Tablebase;
 use clap::Parser;
-use std::{collections::HashMap, io, sync::Arc};
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tokio::{
     net::TcpListener,
     sync::{broadcast, Mutex},
@@ -40,12 +49,44 @@ struct Args {
     /// Server port
     #[arg(short, long)]
     port: String,
+
+    /// Directory where the game's PGN is written when it ends
+    #[arg(long)]
+    pgn_dir: Option<PathBuf>,
+
+    /// Directory of Syzygy tables used to adjudicate the game as soon as its result is known
+    #[cfg(feature = "syzygy")]
+    #[arg(long)]
+    syzygy_dir: Option<PathBuf>,
 }
 
 pub fn clean_terminal() {
     print!("\x1B[2J\x1B[H");
 }
 
+/// Returns the current UTC date as `YYYY.MM.DD`
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    // Days since 1970-01-01 to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
 /// Initialize a Chess game and runs the main loop to keep
 /// listening new Tcp connections
 #[tokio::main]
@@ -59,6 +100,13 @@ async fn main() -> io::Result<()> {
     game.set_board();
     game.start();
 
+    #[cfg(feature = "syzygy")]
+    let tablebase = args
+        .syzygy_dir
+        .map(Tablebase::open)
+        .transpose()
+        .map_err(io::Error::other)?;
+
     let mut passwords = HashMap::new();
     passwords.insert(args.white, Color::White);
     passwords.insert(args.black, Color::Black);
@@ -70,16 +118,27 @@ async fn main() -> io::Result<()> {
     };
 
     let (playing_color_tx, _) = broadcast::channel(MAX_CHANNEL);
+    let address = args.address + ":" + &args.port;
+
+    let pgn_tags = PgnTags {
+        event: "rust-chess game".to_string(),
+        site: address.clone(),
+        date: today(),
+        ..Default::default()
+    };
 
     let ctx = Context {
         passwords,
         auth,
         game,
         playing_color_tx,
+        pgn_dir: args.pgn_dir,
+        pgn_tags,
+        #[cfg(feature = "syzygy")]
+        tablebase,
     };
 
     let rpc = rpc(ctx);
-    let address = args.address + ":" + &args.port;
     let listener = TcpListener::bind(address).await?;
 
     println!("Waiting for connections...\n");
@@ -2,13 +2,25 @@
 //!
 //! Collection of Rpc `Response` to be returned by the server
 //!
-use chess_lib::{game::Game, pieces::Color};
+#[cfg(feature = "syzygy")]
+use chess_lib::tablebase::Tablebase;
+use chess_lib::{
+    game::{Game, GameState},
+    pgn::PgnTags,
+    pieces::Color,
+};
 use chess_server::ChessResponse;
 use core::net::SocketAddr;
 use json_rpc::{JsonRpcError, Response, CONNECTION_CLOSED_BY_SERVER, INVALID_PARAMS};
 use json_rpc_proc_macros::{rpc, rpc_method};
 use serde_json::Value;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tokio::{
     io::WriteHalf,
     net::TcpStream,
@@ -21,6 +33,9 @@ use tokio::{
 
 use super::socket;
 
+/// Movement command used to claim a draw (fifty-move rule or threefold repetition)
+const DRAW_COMMAND: &str = "draw";
+
 pub struct Authentication {
     pub addrs: HashMap<Color, SocketAddr>,
     pub new_addr_channel_tx: Sender<SocketAddr>,
@@ -32,6 +47,12 @@ pub struct Context {
     pub auth: Authentication,
     pub game: Game,
     pub playing_color_tx: Sender<Color>,
+    /// Directory where the game's PGN is written when it ends
+    pub pgn_dir: Option<PathBuf>,
+    pub pgn_tags: PgnTags,
+    /// Syzygy tables used to adjudicate the game as soon as its result is known
+    #[cfg(feature = "syzygy")]
+    pub tablebase: Option<Tablebase>,
 }
 
 /// Register a `SocketAddr` as a whitelisted address to submit requests on behalf
@@ -45,7 +66,7 @@ async fn password_authentication(rpc: &Rpc<'_>, addr: SocketAddr) -> bool {
     ctx.auth
         .addrs
         .get(&color_turn)
-        .map_or(false, |valid_addr| *valid_addr == addr)
+        .is_some_and(|valid_addr| *valid_addr == addr)
 }
 
 /// Checks the validity of a submitted password by a client
@@ -65,6 +86,9 @@ pub async fn password(
                     turn: ctx.game.turn,
                     board: ctx.game.print_board(color),
                     game_state: ctx.game.state,
+                    check: ctx.game.is_in_check(),
+                    last_move: ctx.game.last_move().map(String::from),
+                    outcome: ctx.game.outcome,
                 };
 
                 if let Some(prev_addr) = ctx.auth.addrs.insert(color, addr) {
@@ -95,7 +119,8 @@ pub async fn password(
     }
 }
 
-/// Updates the board state based on a movement submitted by the client
+/// Updates the board state based on a movement submitted by the client, or ends the game
+/// if the client claims a draw
 #[rpc_method]
 pub async fn movement(
     _addr: SocketAddr,
@@ -104,35 +129,61 @@ pub async fn movement(
 ) -> Response {
     let mut ctx = ctx_mutex.lock().await;
     match params.first() {
-        Some(Value::String(movement)) => ctx
-            .game
-            .move_piece(movement.trim().to_string())
-            .map(|_| {
-                let playing_color = Game::static_playing_color(ctx.game.turn);
+        Some(Value::String(movement)) => {
+            let player_color = Game::static_playing_color(ctx.game.turn);
+            let result = match movement.trim() {
+                DRAW_COMMAND => ctx
+                    .game
+                    .claim_draw()
+                    .map(|_| ())
+                    .ok_or("There is no draw to claim".to_string()),
+                movement => ctx
+                    .game
+                    .move_piece(movement.to_string())
+                    .map(|_| {
+                        if let Some(san) = ctx.game.last_move() {
+                            println!("- {:?} played {}", player_color, san);
+                        }
+                    })
+                    .map_err(|err| format!("{}", err)),
+            };
 
-                let chess_response = ChessResponse {
-                    player_color: None,
-                    turn: ctx.game.turn,
-                    board: ctx.game.print_board(!playing_color),
-                    game_state: ctx.game.state,
-                };
+            result
+                .map(|_| {
+                    #[cfg(feature = "syzygy")]
+                    adjudicate(&mut ctx);
+                    if ctx.game.state == GameState::Ended {
+                        save_pgn(&ctx);
+                    }
+                    let playing_color = Game::static_playing_color(ctx.game.turn);
 
-                let _ = ctx.playing_color_tx.send(playing_color).unwrap();
-                let _ = ctx.playing_color_tx.send(!playing_color).unwrap();
+                    let chess_response = ChessResponse {
+                        player_color: None,
+                        turn: ctx.game.turn,
+                        board: ctx.game.print_board(player_color),
+                        game_state: ctx.game.state,
+                        check: ctx.game.is_in_check(),
+                        last_move: ctx.game.last_move().map(String::from),
+                        outcome: ctx.game.outcome,
+                    };
 
-                Response::success(
-                    serde_json::to_value::<ChessResponse>(chess_response).unwrap(),
-                    None,
-                )
-            })
-            .unwrap_or_else(|err| {
-                let error = JsonRpcError {
-                    code: INVALID_PARAMS,
-                    message: format!("{}", err),
-                    data: None,
-                };
-                Response::error(error, None)
-            }),
+                    let _ = ctx.playing_color_tx.send(playing_color).unwrap();
+                    let _ = ctx.playing_color_tx.send(!playing_color).unwrap();
+
+                    Response::success(
+                        serde_json::to_value::<ChessResponse>(chess_response).unwrap(),
+                        None,
+                    )
+                })
+                .unwrap_or_else(|message| {
+                    let error = JsonRpcError {
+                        code: INVALID_PARAMS,
+                        message,
+                        data: None,
+                    };
+                    Response::error(error, None)
+                })
+        }
         _ => {
             let error = JsonRpcError {
                 code: INVALID_PARAMS,
@@ -144,6 +195,35 @@ pub async fn movement(
     }
 }
 
+/// End the game if the `tablebase`, if any, knows its result
+#[cfg(feature = "syzygy")]
+fn adjudicate(ctx: &mut Context) {
+    let Some(tablebase) = &ctx.tablebase else {
+        return;
+    };
+    if let Some(outcome) = ctx.game.adjudicate(tablebase) {
+        println!("- {}", outcome);
+    }
+}
+
+/// Write the game's PGN in the `pgn_dir`, if any
+fn save_pgn(ctx: &Context) {
+    let Some(pgn_dir) = &ctx.pgn_dir else {
+        return;
+    };
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let path = pgn_dir.join(format!("game-{}.pgn", seconds));
+
+    let res =
+        fs::create_dir_all(pgn_dir).and_then(|_| fs::write(&path, ctx.game.to_pgn(&ctx.pgn_tags)));
+    match res {
+        Ok(_) => println!("- Game saved in {:?}", path),
+        Err(e) => println!("Failed to save the game; err = {:?}", e),
+    }
+}
+
 /// Notify the client the opponent has moved a piece
 pub async fn notify_turn(
     rpc: Arc<Rpc<'static>>,
@@ -181,6 +261,9 @@ pub async fn notify_turn(
                     turn: ctx.game.turn,
                     board: ctx.game.print_board(playing_color),
                     game_state: ctx.game.state,
+                    check: ctx.game.is_in_check(),
+                    last_move: ctx.game.last_move().map(String::from),
+                    outcome: ctx.game.outcome,
                 };
                 let response = Response::success(
                     serde_json::to_value::<ChessResponse>(chess_response).unwrap(),
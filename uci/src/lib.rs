@@ -0,0 +1,108 @@
+//! Parsing of the Universal Chess Interface commands and formatting of the engine replies
+//!
+use chess_lib::{
+    engine::{SearchLimits, SearchResult},
+    fen::STARTING_FEN,
+    game::Game,
+    pieces::Color::{self, Black, White},
+};
+use std::time::Duration;
+
+/// Arguments of a `go` command
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct Go {
+    pub limits: SearchLimits,
+    /// Search until `stop`, holding `bestmove` back even if the search finishes before
+    pub infinite: bool,
+}
+
+/// Returns a started `Game` from a FEN
+pub fn new_game(fen: &str) -> Result<Game, String> {
+    let mut game = Game::from_fen(fen).map_err(|error| error.to_string())?;
+    game.start();
+    Ok(game)
+}
+
+/// Parse the arguments of `position [startpos | fen <fen>] [moves <move> ...]`
+pub fn position<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<Game, String> {
+    let fen = match tokens.next() {
+        Some("startpos") => STARTING_FEN.to_string(),
+        Some("fen") => tokens
+            .by_ref()
+            .take_while(|token| *token != "moves")
+            .collect::<Vec<_>>()
+            .join(" "),
+        other => return Err(format!("Unknown position {:?}", other)),
+    };
+
+    let mut game = new_game(&fen)?;
+    for movement in tokens.skip_while(|token| *token == "moves") {
+        game.move_piece(movement.to_string())
+            .map_err(|error| format!("{}: {}", movement, error))?;
+    }
+    Ok(game)
+}
+
+/// Parse `setoption name <name> [value <value>]`, returning the name and the value
+pub fn option(line: &str) -> Option<(&str, &str)> {
+    let (_, option) = line.split_once("name")?;
+    let (name, value) = option.split_once("value").unwrap_or((option, ""));
+    Some((name.trim(), value.trim()))
+}
+
+/// Parse the arguments of `go [depth <plies>] [movetime <ms>] [wtime <ms>] [btime <ms>]
+/// [winc <ms>] [binc <ms>] [movestogo <moves>] [infinite]` for the `Color` to move. Its clock
+/// sets the time budget unless `movetime` is given
+pub fn go<'a>(mut tokens: impl Iterator<Item = &'a str>, color: Color) -> Go {
+    let mut go = Go::default();
+    let mut clock = [None, None];
+    let mut increment = [Duration::ZERO, Duration::ZERO];
+    let mut moves_to_go = None;
+
+    while let Some(token) = tokens.next() {
+        let mut value = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+        match token {
+            "depth" => go.limits.depth = value().map(|depth| depth as u32),
+            "movetime" => go.limits.time = value().map(Duration::from_millis),
+            "wtime" => clock[0] = value().map(Duration::from_millis),
+            "btime" => clock[1] = value().map(Duration::from_millis),
+            "winc" => increment[0] = value().map_or(Duration::ZERO, Duration::from_millis),
+            "binc" => increment[1] = value().map_or(Duration::ZERO, Duration::from_millis),
+            "movestogo" => moves_to_go = value().map(|moves| moves as u32),
+            "infinite" => go.infinite = true,
+            _ => {}
+        }
+    }
+
+    let side = match color {
+        White => 0,
+        Black => 1,
+    };
+    if let (None, Some(remaining)) = (go.limits.time, clock[side]) {
+        go.limits.time = SearchLimits::from_clock(remaining, increment[side], moves_to_go).time;
+    }
+    go
+}
+
+/// Returns the `info` line of a completed iteration
+pub fn info(result: &SearchResult, elapsed: Duration) -> String {
+    let score = match result.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    let millis = elapsed.as_millis().max(1);
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|movement| movement.to_string())
+        .collect();
+    format!(
+        "info depth {} score {} nodes {} time {} nps {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        millis,
+        result.nodes as u128 * 1000 / millis,
+        pv.join(" ")
+    )
+}
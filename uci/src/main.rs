@@ -0,0 +1,134 @@
+//! Rust Chess UCI engine
+//!
+//! Speaks the Universal Chess Interface over stdin/stdout, so the library's `Engine` can be used
+//! from chess GUIs and match runners
+use chess_lib::{
+    book::{BookSelection, PolyglotBook},
+    engine::Engine,
+    fen::STARTING_FEN,
+    game::Game,
+};
+use chess_uci::{go, info, new_game, option, position, Go};
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Search running in the background, which prints `bestmove` when it finishes, or once
+/// stopped when infinite
+struct Search {
+    /// Stops the `Engine`, which clears it as soon as it returns
+    stop: Arc<AtomicBool>,
+    /// Set once `stop` is received
+    stop_requested: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// Read UCI commands until `quit` or the end of the input
+fn main() {
+    let mut game = new_game(STARTING_FEN).expect("Starting position is valid");
+    let mut search: Option<Search> = None;
+    let mut book: Option<PolyglotBook> = None;
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                println!("id name rust-chess");
+                println!("id author NachoPal");
+                println!("option name Book type string default <empty>");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => {
+                stop(&mut search);
+                game = new_game(STARTING_FEN).expect("Starting position is valid");
+            }
+            Some("position") => {
+                stop(&mut search);
+                match position(tokens) {
+                    Ok(new_game) => game = new_game,
+                    Err(error) => println!("info string {}", error),
+                }
+            }
+            Some("setoption") => {
+                if let Some(("Book", path)) = option(&line) {
+                    book = match path {
+                        "" | "<empty>" => None,
+                        path => PolyglotBook::open(path)
+                            .map_err(|error| println!("info string {}", error))
+                            .ok(),
+                    };
+                }
+            }
+            Some("go") => {
+                stop(&mut search);
+                let color = Game::static_playing_color(game.turn);
+                search = Some(start_search(&game, book.as_ref(), go(tokens, color)));
+            }
+            Some("stop") => stop(&mut search),
+            Some("quit") => break,
+            // `debug`, `register`, `ponderhit` and unknown commands are ignored
+            _ => {}
+        }
+    }
+    stop(&mut search);
+}
+
+/// Start searching in the background, unless the `Book` has a movement for the position
+fn start_search(game: &Game, book: Option<&PolyglotBook>, go: Go) -> Search {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64);
+    let book_move = book.and_then(|book| book.select(game, BookSelection::Weighted(seed)));
+
+    let game = game.clone();
+    let mut engine = Engine::default();
+    let stop = engine.stop_handle();
+    let stop_requested = Arc::new(AtomicBool::new(false));
+    let requested = stop_requested.clone();
+    let handle = thread::spawn(move || {
+        let best_move = match book_move {
+            Some(movement) => Some(movement),
+            None => {
+                let start = Instant::now();
+                engine
+                    .search_with(&game, go.limits, |result| {
+                        println!("{}", info(result, start.elapsed()));
+                    })
+                    .best_move
+            }
+        };
+        // `bestmove` must wait for `stop` in infinite mode
+        while go.infinite && !requested.load(Ordering::Relaxed) {
+            thread::park();
+        }
+        match best_move {
+            Some(movement) => println!("bestmove {}", movement),
+            None => println!("bestmove 0000"),
+        }
+    });
+    Search {
+        stop,
+        stop_requested,
+        handle,
+    }
+}
+
+/// Stop the running search, if any, and wait for its `bestmove`
+fn stop(search: &mut Option<Search>) {
+    if let Some(search) = search.take() {
+        search.stop.store(true, Ordering::Relaxed);
+        search.stop_requested.store(true, Ordering::Relaxed);
+        search.handle.thread().unpark();
+        search.handle.join().expect("Search does not panic");
+    }
+}
@@ -0,0 +1,142 @@
+use chess_lib::{
+    engine::SearchLimits,
+    fen::STARTING_FEN,
+    pieces::Color::{Black, White},
+};
+use chess_uci::{go, option, position, Go};
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+fn tokens(command: &str) -> impl Iterator<Item = &str> {
+    command.split_whitespace()
+}
+
+#[test]
+fn position_arguments() {
+    let game = position(tokens("startpos")).expect("Valid position");
+    assert_eq!(game.to_fen(), STARTING_FEN);
+
+    let game = position(tokens("startpos moves e2e4 e7e5 g1f3")).expect("Valid position");
+    assert_eq!(
+        game.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+
+    let fen = "7k/5Q2/6K1/8/8/8/8/8 w - - 0 1";
+    let game = position(tokens(&format!("fen {}", fen))).expect("Valid position");
+    assert_eq!(game.to_fen(), fen);
+    let game = position(tokens(&format!("fen {} moves f7f8", fen))).expect("Valid position");
+    assert_eq!(game.to_fen(), "5Q1k/8/6K1/8/8/8/8/8 b - - 1 1");
+
+    assert_eq!(
+        position(tokens("middlegame")).err(),
+        Some("Unknown position Some(\"middlegame\")".to_string())
+    );
+    assert!(position(tokens("fen 4k3/8/8/8/8/8/8/4X3 w - - 0 1")).is_err());
+    assert!(position(tokens("startpos moves e2e5"))
+        .err()
+        .is_some_and(|error| error.starts_with("e2e5: ")));
+}
+
+#[test]
+fn setoption_arguments() {
+    assert_eq!(
+        option("setoption name Book value /books/my book.bin"),
+        Some(("Book", "/books/my book.bin"))
+    );
+    assert_eq!(option("setoption name Book"), Some(("Book", "")));
+    assert_eq!(option("setoption Book"), None);
+}
+
+#[test]
+fn go_arguments() {
+    let millis = |millis| Some(Duration::from_millis(millis));
+
+    assert_eq!(go(tokens(""), White), Go::default());
+    assert_eq!(
+        go(tokens("depth 5 movetime 1500"), White),
+        Go {
+            limits: SearchLimits {
+                depth: Some(5),
+                time: millis(1500),
+            },
+            infinite: false,
+        }
+    );
+    assert_eq!(
+        go(tokens("infinite"), Black),
+        Go {
+            limits: SearchLimits::default(),
+            infinite: true,
+        }
+    );
+
+    // The clock of the `Color` to move sets the time budget, unless there is a `movetime`
+    let clock = "wtime 60000 btime 30000 winc 1000 binc 0 movestogo 10";
+    assert_eq!(
+        go(tokens(clock), White).limits,
+        SearchLimits::from_clock(Duration::from_secs(60), Duration::from_secs(1), Some(10))
+    );
+    assert_eq!(
+        go(tokens(clock), Black).limits,
+        SearchLimits::from_clock(Duration::from_secs(30), Duration::ZERO, Some(10))
+    );
+    assert_eq!(
+        go(tokens(&format!("{} movetime 200", clock)), White)
+            .limits
+            .time,
+        millis(200)
+    );
+    // Missing or malformed values are ignored
+    assert_eq!(go(tokens("depth x movetime"), White), Go::default());
+}
+
+#[test]
+fn infinite_search_waits_for_stop() {
+    let mut uci = Command::new(env!("CARGO_BIN_EXE_chess-uci"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Binary runs");
+    let mut stdin = uci.stdin.take().expect("Stdin is piped");
+    let stdout = uci.stdout.take().expect("Stdout is piped");
+    let (lines_tx, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = lines_tx.send(line);
+        }
+    });
+    let bestmove = |timeout| {
+        while let Ok(line) = lines.recv_timeout(timeout) {
+            if line.starts_with("bestmove") {
+                return Some(line);
+            }
+        }
+        None
+    };
+
+    // Mate in one is found right away, but not reported until `stop`
+    writeln!(stdin, "position fen 7k/5Q2/6K1/8/8/8/8/8 w - - 0 1").expect("Stdin is open");
+    writeln!(stdin, "go infinite").expect("Stdin is open");
+    assert_eq!(bestmove(Duration::from_millis(500)), None);
+    writeln!(stdin, "stop").expect("Stdin is open");
+    assert_eq!(
+        bestmove(Duration::from_secs(10)).as_deref(),
+        Some("bestmove f7g7")
+    );
+
+    // Other searches answer on their own
+    writeln!(stdin, "go depth 2").expect("Stdin is open");
+    assert_eq!(
+        bestmove(Duration::from_secs(10)).as_deref(),
+        Some("bestmove f7g7")
+    );
+
+    writeln!(stdin, "quit").expect("Stdin is open");
+    assert!(uci.wait().expect("Binary exits").success());
+}
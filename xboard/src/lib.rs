@@ -0,0 +1,101 @@
+//! Time controls and thinking output of the Chess Engine Communication Protocol
+//!
+use chess_lib::{
+    engine::{SearchLimits, SearchResult},
+    game::Game,
+};
+use std::time::Duration;
+
+/// Time control of the engine, set by `level`, `st` and `sd`, and its clock
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct TimeControl {
+    /// Fixed depth set by `sd`
+    pub depth: Option<u32>,
+    /// Fixed time per movement set by `st`
+    pub move_time: Option<Duration>,
+    /// Movements per time control set by `level`, `0` for the whole game
+    pub moves_per_session: u32,
+    /// Increment per movement set by `level`
+    pub increment: Duration,
+    /// Engine's remaining time, updated by `time`
+    pub clock: Duration,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        TimeControl {
+            depth: None,
+            move_time: None,
+            moves_per_session: 0,
+            increment: Duration::ZERO,
+            clock: Duration::from_secs(300),
+        }
+    }
+}
+
+impl TimeControl {
+    /// Parse `level <moves per session> <base time> <increment>`, where the base time is either
+    /// minutes or `minutes:seconds` and the increment is in seconds
+    pub fn level(&mut self, arguments: &str) {
+        let fields: Vec<&str> = arguments.split_whitespace().collect();
+        let [moves, base, increment] = fields.as_slice() else {
+            return;
+        };
+        let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+        self.moves_per_session = moves.parse().unwrap_or(0);
+        self.clock = Duration::from_secs(
+            60 * minutes.parse::<u64>().unwrap_or(0) + seconds.parse::<u64>().unwrap_or(0),
+        );
+        self.increment = increment
+            .parse::<f64>()
+            .ok()
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .unwrap_or(Duration::ZERO);
+        self.move_time = None;
+    }
+
+    /// Returns the `SearchLimits` of the engine's movement in a certain turn
+    pub fn limits(&self, turn: u32) -> SearchLimits {
+        if self.depth.is_some() || self.move_time.is_some() {
+            return SearchLimits {
+                depth: self.depth,
+                time: self.move_time,
+            };
+        }
+        let moves_to_go = (self.moves_per_session > 0).then(|| {
+            let played = turn / 2;
+            self.moves_per_session - played % self.moves_per_session
+        });
+        SearchLimits::from_clock(self.clock, self.increment, moves_to_go)
+    }
+}
+
+/// Returns a started `Game` from a FEN
+pub fn new_game(fen: &str) -> Result<Game, String> {
+    let mut game = Game::from_fen(fen).map_err(|error| error.to_string())?;
+    game.start();
+    Ok(game)
+}
+
+/// Returns the thinking output of a completed iteration: depth, score, time in centiseconds,
+/// nodes and principal variation. Mates are scored `100000 + moves`, as usual in CECP
+pub fn thinking(result: &SearchResult, elapsed: Duration) -> String {
+    let score = match result.mate_in() {
+        Some(moves) if moves > 0 => 100_000 + moves,
+        Some(moves) => -100_000 + moves,
+        None => result.score,
+    };
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|movement| movement.to_string())
+        .collect();
+    format!(
+        "{} {} {} {} {}",
+        result.depth,
+        score,
+        elapsed.as_millis() / 10,
+        result.nodes,
+        pv.join(" ")
+    )
+}
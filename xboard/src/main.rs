@@ -0,0 +1,244 @@
+//! Rust Chess XBoard engine
+//!
+//! Speaks the Chess Engine Communication Protocol (CECP) over stdin/stdout, so the library's
+//! `Engine` can be used from xboard compatible GUIs and adjudicators
+//!
+//! Searches run in the background: `?`, `ping`, `post`, `nopost`, `time` and `otim` are handled
+//! while thinking, and any other command abandons the search
+use chess_lib::{
+    engine::{Engine, SearchResult},
+    fen::STARTING_FEN,
+    game::{Game, GameState},
+    pieces::Color::{self, Black},
+};
+use chess_xboard::{new_game, thinking, TimeControl};
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Input of the main loop
+enum Event {
+    /// A command line
+    Command(String),
+    /// The search with a certain id has finished
+    Searched(u32),
+}
+
+/// Search running in the background, which sends `Event::Searched` when it finishes
+struct Search {
+    id: u32,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<SearchResult>,
+}
+
+/// State of the engine between commands
+struct XBoard {
+    game: Game,
+    /// `Color` played by the engine, `None` in force mode
+    engine_color: Option<Color>,
+    /// Whether thinking output is printed
+    post: bool,
+    time_control: TimeControl,
+    /// Search of the engine's movement, if thinking
+    search: Option<Search>,
+    /// Id of the last search started
+    searches: u32,
+    events: Sender<Event>,
+}
+
+/// Read CECP commands until `quit` or the end of the input
+fn main() {
+    let (events, inbox) = mpsc::channel();
+    let commands = events.clone();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            if commands.send(Event::Command(line)).is_err() {
+                return;
+            }
+        }
+        let _ = commands.send(Event::Command("quit".to_string()));
+    });
+
+    let mut xboard = XBoard {
+        game: new_game(STARTING_FEN).expect("Starting position is valid"),
+        engine_color: Some(Black),
+        post: false,
+        time_control: TimeControl::default(),
+        search: None,
+        searches: 0,
+        events,
+    };
+    for event in inbox {
+        match event {
+            Event::Command(line) => {
+                let (command, arguments) = line
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((line.trim(), ""));
+                if command == "quit" {
+                    xboard.abandon();
+                    break;
+                }
+                xboard.command(command, arguments.trim());
+            }
+            Event::Searched(id) => {
+                if xboard.search.as_ref().is_some_and(|search| search.id == id) {
+                    xboard.play_search();
+                }
+            }
+        }
+    }
+}
+
+impl XBoard {
+    /// Handle a single command
+    fn command(&mut self, command: &str, arguments: &str) {
+        match command {
+            // Handled while thinking
+            "?" => {
+                if let Some(search) = &self.search {
+                    search.stop.store(true, Ordering::Relaxed);
+                    self.play_search();
+                }
+                return;
+            }
+            "ping" => {
+                println!("pong {}", arguments);
+                return;
+            }
+            "post" => {
+                self.post = true;
+                return;
+            }
+            "nopost" => {
+                self.post = false;
+                return;
+            }
+            "time" => {
+                if let Ok(centiseconds) = arguments.parse::<u64>() {
+                    self.time_control.clock = Duration::from_millis(10 * centiseconds);
+                }
+                return;
+            }
+            "otim" => return,
+            _ => self.abandon(),
+        }
+
+        match command {
+            "protover" => println!(
+                "feature myname=\"rust-chess\" setboard=1 usermove=1 ping=1 playother=1 \
+                 colors=0 sigint=0 sigterm=0 done=1"
+            ),
+            "new" => {
+                self.game = new_game(STARTING_FEN).expect("Starting position is valid");
+                self.engine_color = Some(Black);
+                self.time_control.depth = None;
+            }
+            "setboard" => match new_game(arguments) {
+                Ok(game) => self.game = game,
+                Err(error) => println!("tellusererror Illegal position: {}", error),
+            },
+            "force" | "result" => self.engine_color = None,
+            "go" => {
+                self.engine_color = Some(self.side_to_move());
+                self.think();
+            }
+            "playother" => self.engine_color = Some(!self.side_to_move()),
+            "usermove" => self.user_move(arguments),
+            "undo" => {
+                self.game.undo();
+            }
+            "remove" => {
+                self.game.undo();
+                self.game.undo();
+            }
+            "level" => self.time_control.level(arguments),
+            "st" => self.time_control.move_time = arguments.parse().ok().map(Duration::from_secs),
+            "sd" => self.time_control.depth = arguments.parse().ok(),
+            // `xboard`, `accepted`, `rejected`, `random`, `hard`, `easy`, `computer` and unknown
+            // commands are ignored
+            _ => {}
+        }
+    }
+
+    /// Returns the `Color` to move
+    fn side_to_move(&self) -> Color {
+        Game::static_playing_color(self.game.turn)
+    }
+
+    /// Play the opponent's movement, and answer it if it is the engine's turn
+    fn user_move(&mut self, movement: &str) {
+        if let Err(error) = self.game.move_piece(movement.to_string()) {
+            println!("Illegal move ({}): {}", error, movement);
+            return;
+        }
+        self.report_result();
+        if self.engine_color == Some(self.side_to_move()) {
+            self.think();
+        }
+    }
+
+    /// Start searching the engine's movement in the background
+    fn think(&mut self) {
+        if self.game.state == GameState::Ended {
+            return;
+        }
+        self.searches += 1;
+        let id = self.searches;
+        let game = self.game.clone();
+        let limits = self.time_control.limits(game.turn);
+        let post = self.post;
+        let events = self.events.clone();
+        let mut engine = Engine::default();
+        let stop = engine.stop_handle();
+        let handle = thread::spawn(move || {
+            let start = Instant::now();
+            let result = engine.search_with(&game, limits, |result| {
+                if post {
+                    println!("{}", thinking(result, start.elapsed()));
+                }
+            });
+            let _ = events.send(Event::Searched(id));
+            result
+        });
+        self.search = Some(Search { id, stop, handle });
+    }
+
+    /// Wait for the search and play its movement
+    fn play_search(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        let result = search.handle.join().expect("Search does not panic");
+        let Some(movement) = result.best_move else {
+            return;
+        };
+        self.game
+            .move_piece(movement.to_string())
+            .expect("Engine plays legal movements");
+        println!("move {}", movement);
+        self.report_result();
+    }
+
+    /// Stop the search, if any, discarding its movement
+    fn abandon(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            search.handle.join().expect("Search does not panic");
+        }
+    }
+
+    /// Print the result once the `Game` has ended
+    fn report_result(&self) {
+        if let Some(outcome) = self.game.outcome {
+            println!("{} {{{}}}", self.game.pgn_result(), outcome.reason);
+        }
+    }
+}
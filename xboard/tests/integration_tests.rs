@@ -0,0 +1,136 @@
+use chess_lib::engine::{Engine, SearchLimits};
+use chess_xboard::{new_game, thinking, TimeControl};
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn level_arguments() {
+    let mut time_control = TimeControl {
+        move_time: Some(Duration::from_secs(5)),
+        ..TimeControl::default()
+    };
+    time_control.level("40 5 0");
+    assert_eq!(
+        time_control,
+        TimeControl {
+            moves_per_session: 40,
+            clock: Duration::from_secs(300),
+            ..TimeControl::default()
+        }
+    );
+
+    time_control.level("0 2:30 1.5");
+    assert_eq!(time_control.moves_per_session, 0);
+    assert_eq!(time_control.clock, Duration::from_secs(150));
+    assert_eq!(time_control.increment, Duration::from_millis(1500));
+
+    // Malformed increments count as none, and incomplete commands are ignored
+    time_control.level("0 1 -3");
+    assert_eq!(time_control.clock, Duration::from_secs(60));
+    assert_eq!(time_control.increment, Duration::ZERO);
+    time_control.level("0 10");
+    assert_eq!(time_control.clock, Duration::from_secs(60));
+}
+
+#[test]
+fn limits_of_time_controls() {
+    // `sd` and `st` take precedence over the clock
+    let time_control = TimeControl {
+        depth: Some(4),
+        ..TimeControl::default()
+    };
+    assert_eq!(
+        time_control.limits(0),
+        SearchLimits {
+            depth: Some(4),
+            time: None,
+        }
+    );
+    let time_control = TimeControl {
+        move_time: Some(Duration::from_secs(2)),
+        ..TimeControl::default()
+    };
+    assert_eq!(
+        time_control.limits(0),
+        SearchLimits {
+            depth: None,
+            time: Some(Duration::from_secs(2)),
+        }
+    );
+
+    // The clock is split among the movements left to the next time control
+    let mut time_control = TimeControl::default();
+    time_control.level("40 5 2");
+    let clock = |moves_to_go| {
+        SearchLimits::from_clock(
+            Duration::from_secs(300),
+            Duration::from_secs(2),
+            moves_to_go,
+        )
+    };
+    assert_eq!(time_control.limits(0), clock(Some(40)));
+    assert_eq!(time_control.limits(21), clock(Some(30)));
+    assert_eq!(time_control.limits(80), clock(Some(40)));
+    time_control.moves_per_session = 0;
+    assert_eq!(time_control.limits(80), clock(None));
+}
+
+#[test]
+fn thinking_output() {
+    let game = new_game("7k/5Q2/6K1/8/8/8/8/8 w - - 0 1").expect("Valid position");
+    let mut result = Engine::default().search(
+        &game,
+        SearchLimits {
+            depth: Some(2),
+            time: None,
+        },
+    );
+    result.nodes = 42;
+    assert_eq!(
+        thinking(&result, Duration::from_millis(1234)),
+        "1 100001 123 42 f7g7"
+    );
+
+    // Mated scores are negative, and other scores are in centipawns
+    result.score = -result.score;
+    assert!(thinking(&result, Duration::ZERO).starts_with("1 -100001 0 42 "));
+    result.score = 35;
+    assert!(thinking(&result, Duration::ZERO).starts_with("1 35 0 42 "));
+}
+
+#[test]
+fn commands_while_thinking() {
+    let mut xboard = Command::new(env!("CARGO_BIN_EXE_chess-xboard"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Binary runs");
+    let mut stdin = xboard.stdin.take().expect("Stdin is piped");
+    let stdout = xboard.stdout.take().expect("Stdout is piped");
+    let (lines_tx, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = lines_tx.send(line);
+        }
+    });
+    let next_line = || lines.recv_timeout(Duration::from_secs(10)).ok();
+
+    // `ping` is answered right away, and `?` plays the best movement so far
+    writeln!(stdin, "xboard\nst 60\ngo\nping 1").expect("Stdin is open");
+    assert_eq!(next_line().as_deref(), Some("pong 1"));
+    writeln!(stdin, "?").expect("Stdin is open");
+    assert!(next_line().is_some_and(|line| line.starts_with("move ")));
+
+    // `force` abandons the search without moving
+    writeln!(stdin, "go\nforce\nping 2").expect("Stdin is open");
+    assert_eq!(next_line().as_deref(), Some("pong 2"));
+    assert_eq!(lines.recv_timeout(Duration::from_millis(500)).ok(), None);
+
+    writeln!(stdin, "quit").expect("Stdin is open");
+    assert!(xboard.wait().expect("Binary exits").success());
+}